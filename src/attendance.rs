@@ -0,0 +1,282 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+extern crate serde_json;
+
+use std::fs::File;
+use std::io;
+use std::io::{Read, Write};
+
+use self::serde_json::{Map, Value};
+
+use types::*;
+use parser::{get_students, InputFormat};
+use migrate::parse_input_value;
+use statistics::{get_quota, get_required};
+
+/// the sessions of a student, in the order of COURSE_TYPES
+#[derive(Default)]
+struct AttendanceCounts {
+    planned: [u64; 4],
+    visited: [u64; 4],
+    absent: [u64; 4],
+    excused: [u64; 4],
+    remaining: [u64; 4],
+}
+
+/// reads an attendance file in the format of its extension (json, toml or yaml)
+pub fn read_attendance(path: &str) -> JsonAttendance {
+    let mut text = String::new();
+    if let Err(error) = File::open(path).and_then(|mut file| file.read_to_string(&mut text)) {
+        panic!("Unable to read attendance {}: {}", path, error);
+    }
+    let attendance = parse_input_value(&text, InputFormat::from_path(path))
+        .and_then(|value| serde_json::from_value(value).map_err(|error| error.to_string()));
+    match attendance {
+        Ok(attendance) => attendance,
+        Err(error) => panic!("Unable to parse attendance {}: {}", path, error),
+    }
+}
+
+fn get_type_index(course_type: CourseType) -> usize {
+    COURSE_TYPES
+        .iter()
+        .position(|other| *other == course_type)
+        .unwrap_or(0)
+}
+
+/// Compares the attendance with the planned courses until the last week that took place.
+/// Planned participants that are not listed as absent or excused count as present, the
+/// planned sessions after that week count as remaining. Entries that do not match the
+/// distribution are reported as problems.
+pub fn reconcile(parsed_data: &JsonData, schedule: &JsonSchedule, attendance: &JsonAttendance) -> JsonAttendanceReport {
+    let last_week = attendance
+        .bisWoche
+        .or_else(|| attendance.termine.iter().map(|entry| entry.termin.woche).max())
+        .unwrap_or_else(|| parsed_data.wochen.kwAnfang.saturating_sub(1));
+    let students = get_students(parsed_data);
+    let mut counts: Vec<AttendanceCounts> = students.iter().map(|_| AttendanceCounts::default()).collect();
+    let get_position = |number: u64| students.iter().position(|student| student.number == number);
+    let mut problems = Vec::new();
+
+    for (index, entry) in attendance.termine.iter().enumerate() {
+        let slot = entry.termin;
+        if attendance.termine[..index].iter().any(|other| other.termin == slot) {
+            problems.push(format!("{}: recorded twice, only the first entry counts", slot));
+            continue;
+        }
+        if slot.woche > last_week {
+            problems.push(format!("{}: after KW {}, the last week that took place", slot, last_week));
+            continue;
+        }
+        let participants = schedule
+            .wochen
+            .iter()
+            .filter(|week| week.nummer == slot.woche)
+            .flat_map(|week| week.kurse.iter())
+            .find(|course| course.tag == slot.tag && course.kurs == slot.kurs && course.beginn == slot.beginn)
+            .map(|course| &course.teilnehmer);
+        let participants = match participants {
+            Some(participants) => participants,
+            None => {
+                problems.push(format!("{}: there is no such course in the distribution", slot));
+                continue;
+            }
+        };
+        for number in entry.anwesend.iter().chain(entry.abwesend.iter()).chain(entry.entschuldigt.iter()) {
+            if get_position(*number).is_none() {
+                problems.push(format!("{}: there is no student {}", slot, number));
+            }
+        }
+        for number in entry.abwesend.iter().chain(entry.entschuldigt.iter()) {
+            if !participants.contains(number) && get_position(*number).is_some() {
+                problems.push(format!("{}: student {} is not a participant", slot, number));
+            }
+        }
+        for number in entry.abwesend.iter().filter(|number| entry.entschuldigt.contains(number)) {
+            problems.push(format!("{}: student {} is absent and excused, the absence counts", slot, number));
+        }
+        // students that visited the course instead of another one
+        for number in entry.anwesend.iter().filter(|number| !participants.contains(number)) {
+            if let Some(position) = get_position(*number) {
+                counts[position].visited[get_type_index(slot.kurs)] += 1;
+            }
+        }
+    }
+
+    for week in schedule.wochen.iter() {
+        for course in week.kurse.iter() {
+            let type_index = get_type_index(course.kurs);
+            let entry = attendance.termine.iter().find(|entry| {
+                entry.termin.woche == week.nummer
+                    && entry.termin.tag == course.tag
+                    && entry.termin.kurs == course.kurs
+                    && entry.termin.beginn == course.beginn
+            });
+            for number in course.teilnehmer.iter() {
+                let student = match get_position(*number) {
+                    Some(position) => &mut counts[position],
+                    None => continue,
+                };
+                if week.nummer > last_week {
+                    student.remaining[type_index] += 1;
+                    continue;
+                }
+                student.planned[type_index] += 1;
+                match entry {
+                    Some(entry) if entry.abwesend.contains(number) => student.absent[type_index] += 1,
+                    Some(entry) if entry.entschuldigt.contains(number) => student.excused[type_index] += 1,
+                    _ => student.visited[type_index] += 1,
+                }
+            }
+        }
+    }
+
+    let studenten = students
+        .iter()
+        .zip(counts.iter())
+        .map(|(student, counts)| {
+            let mut quotas = [None; 4];
+            let mut deficits = [0; 4];
+            for (index, course_type) in COURSE_TYPES.iter().enumerate() {
+                quotas[index] = get_quota(parsed_data, student, *course_type);
+                if let Some(required) = get_required(parsed_data, student, *course_type) {
+                    deficits[index] = required.saturating_sub(counts.visited[index] + counts.remaining[index]);
+                }
+            }
+            JsonStudentAttendance {
+                nummer: student.number,
+                geplant: JsonCourseCounts::from(counts.planned),
+                besucht: JsonCourseCounts::from(counts.visited),
+                abwesend: JsonCourseCounts::from(counts.absent),
+                entschuldigt: JsonCourseCounts::from(counts.excused),
+                ausstehend: JsonCourseCounts::from(counts.remaining),
+                soll: JsonQuotas::from(quotas),
+                defizit: JsonCourseCounts::from(deficits),
+            }
+        })
+        .collect();
+    JsonAttendanceReport {
+        bisWoche: last_week,
+        studenten,
+        probleme: problems,
+    }
+}
+
+/// Prints the visited and planned sessions of every student, the absences and the
+/// deficits against the quotas.
+pub fn print_attendance<W: Write>(file: &mut W, report: &JsonAttendanceReport) -> io::Result<()> {
+    writeln!(file, "Anwesenheit bis KW {} (besucht/geplant)", report.bisWoche)?;
+    write!(file, "Student")?;
+    for course_type in COURSE_TYPES.iter() {
+        write!(file, " {}", course_type)?;
+    }
+    writeln!(file, " Abwesend   Entschuldigt")?;
+    for student in report.studenten.iter() {
+        write!(file, "{:>7}", student.nummer)?;
+        for course_type in COURSE_TYPES.iter() {
+            let cell = format!("{}/{}", student.besucht.get(*course_type), student.geplant.get(*course_type));
+            write!(file, " {:<10}", cell)?;
+        }
+        writeln!(file, " {:<10} {}", student.abwesend.total(), student.entschuldigt.total())?;
+    }
+    writeln!(file)?;
+    writeln!(file, "Defizite (fehlen zum Kontingent, auch wenn alle ausstehenden Sitzungen besucht werden)")?;
+    let mut has_deficits = false;
+    for student in report.studenten.iter().filter(|student| student.defizit.total() > 0) {
+        let deficits: Vec<String> = COURSE_TYPES
+            .iter()
+            .filter(|course_type| student.defizit.get(**course_type) > 0)
+            .map(|course_type| format!("{:?} {}", course_type, student.defizit.get(*course_type)))
+            .collect();
+        writeln!(file, "Student {}: {}", student.nummer, deficits.join(", "))?;
+        has_deficits = true;
+    }
+    if !has_deficits {
+        writeln!(file, "keine")?;
+    }
+    if !report.probleme.is_empty() {
+        writeln!(file)?;
+        writeln!(file, "Probleme")?;
+        for problem in report.probleme.iter() {
+            writeln!(file, "{}", problem)?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds the deficits of the students to their extra sessions ("zusaetzlich") in the input.
+/// The students with a deficit are prioritized if the remaining weeks are distributed again
+/// with it, no matter if they missed sessions or the distribution planned too few.
+/// Returns the numbers of these students.
+pub fn add_deficits(input: &mut Value, report: &JsonAttendanceReport) -> Vec<u64> {
+    let object = match input.as_object_mut() {
+        Some(object) => object,
+        None => panic!("the input is not an object"),
+    };
+    let key = if object.contains_key("students") { "students" } else { "studenten" };
+    let entries = match object
+        .entry(key.to_string())
+        .or_insert_with(|| Value::Array(Vec::new()))
+        .as_array_mut()
+    {
+        Some(entries) => entries,
+        None => panic!("{} of the input is not a list", key),
+    };
+    let mut prioritized = Vec::new();
+    for student in report.studenten.iter() {
+        let deficits: Vec<(CourseType, u64)> = COURSE_TYPES
+            .iter()
+            .map(|course_type| (*course_type, student.defizit.get(*course_type)))
+            .filter(|&(_, deficit)| deficit > 0)
+            .collect();
+        if deficits.is_empty() {
+            continue;
+        }
+        let position = entries.iter().position(|entry| {
+            entry.get("nummer").or_else(|| entry.get("number")).and_then(Value::as_u64) == Some(student.nummer)
+        });
+        let position = match position {
+            Some(position) => position,
+            None => {
+                let mut entry = Map::new();
+                entry.insert("nummer".to_string(), Value::from(student.nummer));
+                entries.push(Value::Object(entry));
+                entries.len() - 1
+            }
+        };
+        let entry = match entries[position].as_object_mut() {
+            Some(entry) => entry,
+            None => panic!("the entry of student {} in {} is not an object", student.nummer, key),
+        };
+        let key = if entry.contains_key("extra") { "extra" } else { "zusaetzlich" };
+        let extra = match entry
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+        {
+            Some(extra) => extra,
+            None => panic!("{} of student {} is not an object", key, student.nummer),
+        };
+        for (course_type, deficit) in deficits {
+            let name = format!("{:?}", course_type);
+            let sessions = extra.get(&name).and_then(Value::as_u64).unwrap_or(0);
+            extra.insert(name, Value::from(sessions + deficit));
+        }
+        prioritized.push(student.nummer);
+    }
+    prioritized
+}
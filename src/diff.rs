@@ -0,0 +1,227 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::io;
+use std::io::Write;
+
+use types::*;
+
+/// every participation of a student in a course of the schedule
+fn get_assignments(schedule: &JsonSchedule) -> Vec<(JsonCourseSlot, u64)> {
+    let mut assignments = Vec::new();
+    for week in schedule.wochen.iter() {
+        for course in week.kurse.iter() {
+            let slot = JsonCourseSlot {
+                woche: week.nummer,
+                tag: course.tag,
+                kurs: course.kurs,
+                beginn: course.beginn,
+            };
+            for student in course.teilnehmer.iter() {
+                assignments.push((slot, *student));
+            }
+        }
+    }
+    assignments
+}
+
+/// sorts courses by their time and then in the order of COURSE_TYPES
+fn get_slot_key(slot: &JsonCourseSlot) -> (u64, u64, u8, usize) {
+    let type_index = COURSE_TYPES
+        .iter()
+        .position(|course_type| *course_type == slot.kurs)
+        .unwrap_or(0);
+    (slot.woche, slot.tag, slot.beginn, type_index)
+}
+
+/// Lists the assignments that only the new schedule has (added) and those that only the
+/// old schedule has (removed), once per student and once per course.
+pub fn diff_schedules(old: &JsonSchedule, new: &JsonSchedule) -> JsonDiff {
+    let old_assignments = get_assignments(old);
+    let new_assignments = get_assignments(new);
+    let added: Vec<&(JsonCourseSlot, u64)> = new_assignments
+        .iter()
+        .filter(|assignment| !old_assignments.contains(assignment))
+        .collect();
+    let removed: Vec<&(JsonCourseSlot, u64)> = old_assignments
+        .iter()
+        .filter(|assignment| !new_assignments.contains(assignment))
+        .collect();
+
+    let mut students: Vec<JsonStudentDiff> = Vec::new();
+    let mut slots: Vec<JsonSlotDiff> = Vec::new();
+    for (is_added, &&(slot, student)) in added
+        .iter()
+        .map(|assignment| (true, assignment))
+        .chain(removed.iter().map(|assignment| (false, assignment)))
+    {
+        let position = match students.iter().position(|entry| entry.nummer == student) {
+            Some(position) => position,
+            None => {
+                students.push(JsonStudentDiff {
+                    nummer: student,
+                    hinzugefuegt: Vec::new(),
+                    entfernt: Vec::new(),
+                });
+                students.len() - 1
+            }
+        };
+        let entry = &mut students[position];
+        if is_added {
+            entry.hinzugefuegt.push(slot);
+        } else {
+            entry.entfernt.push(slot);
+        }
+        let position = match slots.iter().position(|entry| entry.termin == slot) {
+            Some(position) => position,
+            None => {
+                slots.push(JsonSlotDiff {
+                    termin: slot,
+                    hinzugefuegt: Vec::new(),
+                    entfernt: Vec::new(),
+                });
+                slots.len() - 1
+            }
+        };
+        let entry = &mut slots[position];
+        if is_added {
+            entry.hinzugefuegt.push(student);
+        } else {
+            entry.entfernt.push(student);
+        }
+    }
+    students.sort_by_key(|entry| entry.nummer);
+    for entry in students.iter_mut() {
+        entry.hinzugefuegt.sort_by_key(get_slot_key);
+        entry.entfernt.sort_by_key(get_slot_key);
+    }
+    slots.sort_by_key(|entry| get_slot_key(&entry.termin));
+    for entry in slots.iter_mut() {
+        entry.hinzugefuegt.sort();
+        entry.entfernt.sort();
+    }
+    JsonDiff {
+        zusammenfassung: JsonDiffSummary {
+            hinzugefuegt: added.len() as u64,
+            entfernt: removed.len() as u64,
+            studenten: students.len() as u64,
+            termine: slots.len() as u64,
+        },
+        studenten: students,
+        termine: slots,
+    }
+}
+
+/// writes the changes per student and per course in a human readable form
+pub fn print_diff<T: Write>(file: &mut T, diff: &JsonDiff) -> Result<(), io::Error> {
+    let summary = &diff.zusammenfassung;
+    writeln!(
+        file,
+        "{} hinzugefügt, {} entfernt, {} Studenten und {} Termine betroffen",
+        summary.hinzugefuegt, summary.entfernt, summary.studenten, summary.termine
+    )?;
+    if diff.studenten.is_empty() {
+        return Ok(());
+    }
+    writeln!(file)?;
+    writeln!(file, "Studenten")?;
+    for entry in diff.studenten.iter() {
+        for slot in entry.hinzugefuegt.iter() {
+            writeln!(file, "{:>3} + {}", entry.nummer, slot)?;
+        }
+        for slot in entry.entfernt.iter() {
+            writeln!(file, "{:>3} - {}", entry.nummer, slot)?;
+        }
+    }
+    writeln!(file)?;
+    writeln!(file, "Termine")?;
+    for entry in diff.termine.iter() {
+        let mut changes = Vec::new();
+        if !entry.hinzugefuegt.is_empty() {
+            changes.push(format!("+ {:?}", entry.hinzugefuegt));
+        }
+        if !entry.entfernt.is_empty() {
+            changes.push(format!("- {:?}", entry.entfernt));
+        }
+        writeln!(file, "{}: {}", entry.termin, changes.join(" "))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::*;
+    use distribution::Options;
+    use fixtures::{distribute_quietly, get_input};
+
+    fn get_schedule() -> JsonSchedule {
+        distribute_quietly(&get_input(serde_json::json!({})), Options::default()).schedule
+    }
+
+    /// the participants of the first course of the type in the week
+    fn get_participants(schedule: &mut JsonSchedule, week: usize, course_type: CourseType) -> &mut Vec<u64> {
+        let course = schedule.wochen[week]
+            .kurse
+            .iter_mut()
+            .find(|course| course.kurs == course_type && !course.teilnehmer.is_empty())
+            .unwrap();
+        &mut course.teilnehmer
+    }
+
+    #[test]
+    fn an_unchanged_schedule_has_no_differences() {
+        let schedule = get_schedule();
+        let diff = diff_schedules(&schedule, &schedule);
+        let summary = &diff.zusammenfassung;
+        assert_eq!((summary.hinzugefuegt, summary.entfernt, summary.studenten, summary.termine), (0, 0, 0, 0));
+        assert!(diff.studenten.is_empty() && diff.termine.is_empty());
+    }
+
+    #[test]
+    fn counts_a_moved_student_once_per_student_and_course() {
+        let old = get_schedule();
+        let mut new = old.clone();
+        let student = get_participants(&mut new, 2, CourseType::Zahnerhalt).remove(0);
+        get_participants(&mut new, 4, CourseType::Zahnerhalt).push(student);
+        let diff = diff_schedules(&old, &new);
+        let summary = &diff.zusammenfassung;
+        assert_eq!((summary.hinzugefuegt, summary.entfernt), (1, 1));
+        assert_eq!((summary.studenten, summary.termine), (1, 2));
+        assert_eq!(diff.studenten[0].nummer, student);
+        assert_eq!(diff.studenten[0].hinzugefuegt[0].woche, new.wochen[4].nummer);
+        assert_eq!(diff.studenten[0].entfernt[0].woche, old.wochen[2].nummer);
+        let mut text = Vec::new();
+        print_diff(&mut text, &diff).unwrap();
+        assert!(String::from_utf8(text)
+            .unwrap()
+            .starts_with("1 hinzugefügt, 1 entfernt, 1 Studenten und 2 Termine betroffen"));
+    }
+
+    #[test]
+    fn counts_both_students_of_a_swap() {
+        let old = get_schedule();
+        let mut new = old.clone();
+        let first = get_participants(&mut new, 2, CourseType::Exkurs)[0];
+        let second = get_participants(&mut new, 3, CourseType::Exkurs)[0];
+        get_participants(&mut new, 2, CourseType::Exkurs)[0] = second;
+        get_participants(&mut new, 3, CourseType::Exkurs)[0] = first;
+        let summary = diff_schedules(&old, &new).zusammenfassung;
+        assert_eq!((summary.hinzugefuegt, summary.entfernt), (2, 2));
+        assert_eq!((summary.studenten, summary.termine), (2, 2));
+    }
+}
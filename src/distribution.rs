@@ -0,0 +1,794 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::RefCell;
+use std::collections::LinkedList;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::rc::Rc;
+
+use types::*;
+use parser::*;
+use pins::*;
+use schedule::*;
+use statistics::*;
+
+/// prints the progress of the distribution unless it is quiet
+macro_rules! progress {
+    ($options:expr, $($argument:tt)*) => (
+        if !$options.quiet {
+            println!($($argument)*);
+        }
+    );
+}
+
+/// Counts the session of the group, cancelled courses only count as lost session.
+fn count_session(course: &Course, group: &Group, context: &Context) {
+    if course.kind == CourseKind::Cancelled {
+        group.lost.set(group.lost.get() + 1);
+        return;
+    }
+    if group.has_outstanding() && (course.kind == CourseKind::MakeUp || context.makeup_priority) {
+        group.made_up.set(group.made_up.get() + 1);
+    }
+    group.sessions.set(group.sessions.get() + 1);
+}
+
+/// Adds the participants of the group that are enrolled in the current week to the course.
+/// For cancelled courses the group only records the lost session.
+fn take_seat(course: &Course, group: &Group, context: &Context) {
+    debug_assert_eq!(group.group_type, course.course_type);
+    if course.kind != CourseKind::Cancelled {
+        let mut course_participants = course.participants.borrow_mut();
+        let group_participants = group.get_enrolled(context.week_number);
+        for student in group_participants.iter() {
+            course_participants.push_back(student.clone());
+        }
+        if group.rotating_roles && !group_participants.is_empty() {
+            // the participants take turns, so the operator changes with every session
+            let turn = (group.sessions.get() % group_participants.len() as u64) as usize;
+            if let Some(operator) = group_participants.get(turn) {
+                course.operators.borrow_mut().push_back(operator.clone());
+            }
+        }
+    }
+    count_session(course, group, context);
+}
+
+/// subfunction of distribute_courses
+/// returns the left space (seats), or an error if the pinned groups do not fit
+fn distribute_course(
+    course: &Course,
+    day_index: usize,
+    day: &Day,
+    context: &Context,
+    participants: &mut LinkedList<Group>,
+    space_count: u8,
+) -> Result<u8, String> {
+    let mut space_count = space_count;
+    let mut seat = 0;
+    // records the seat if the decisions are explained
+    let record = |seat: u64,
+                  queue: Vec<Vec<u64>>,
+                  skipped: Vec<JsonSkip>,
+                  group: Option<&Group>,
+                  pinned: bool| {
+        if context.explain {
+            context.decisions.borrow_mut().push(JsonDecision {
+                woche: context.week_number,
+                tag: day_index as u64 + 1,
+                kurs: course.course_type,
+                beginn: course.beginning,
+                art: course.kind,
+                platz: seat,
+                warteschlange: queue,
+                uebersprungen: skipped,
+                vergeben: group.map(Group::get_numbers),
+                fixiert: pinned,
+            });
+        }
+    };
+    let get_queue = |participants: &LinkedList<Group>| -> Vec<Vec<u64>> {
+        if context.explain {
+            participants.iter().map(Group::get_numbers).collect()
+        } else {
+            Vec::new()
+        }
+    };
+    // pinned groups get their seats first and are moved to the back like every other
+    // distributed group
+    let queue = get_queue(participants);
+    let mut rotation = LinkedList::new();
+    let mut pinned = LinkedList::new();
+    while let Some(group) = participants.pop_front() {
+        if is_pinned(&group, course) {
+            pinned.push_back(group);
+        } else {
+            rotation.push_back(group);
+        }
+    }
+    if pinned.len() > space_count as usize {
+        return Err(format!(
+            "KW {} day {} {:?} {}: {} pinned groups exceed the {} seats",
+            context.week_number,
+            day_index + 1,
+            course.course_type,
+            course.beginning,
+            pinned.len(),
+            space_count
+        ));
+    }
+    for group in pinned.iter() {
+        space_count -= 1;
+        seat += 1;
+        take_seat(course, group, context);
+        record(seat, queue.clone(), Vec::new(), Some(group), true);
+    }
+    rotation.append(&mut pinned);
+    *participants = rotation;
+    // groups that missed their turn because they were busy in a course of another type,
+    // skipped groups stay in place so their addresses identify them
+    let mut conflicts: Vec<*const Group> = Vec::new();
+    // the reason why a group can not take a seat, none if it can
+    let get_obstacle = |group: &Group| {
+        if let Some(course_type) = group.get_occupation(course, day) {
+            Some(JsonSkipReason::Belegt(course_type))
+        } else if is_forbidden(group, course) {
+            Some(JsonSkipReason::Verboten)
+        } else if group.get_enrolled(context.week_number).is_empty() {
+            Some(JsonSkipReason::NichtEingeschrieben)
+        } else if course.kind == CourseKind::MakeUp && !group.has_outstanding() {
+            Some(JsonSkipReason::KeinAusfall)
+        } else {
+            None
+        }
+    };
+    let is_available = |group: &Group| get_obstacle(group).is_none();
+    while space_count > 0 {
+        space_count -= 1;
+        seat += 1;
+        let queue = get_queue(participants);
+        // groups that lost sessions come first if they are prioritized
+        let mut splitter = None;
+        if context.makeup_priority && course.kind == CourseKind::Regular {
+            splitter = participants
+                .iter()
+                .position(|group| group.has_outstanding() && is_available(group));
+        }
+        // then the groups that still need sessions for their quotas
+        if splitter.is_none() && context.quota_priority {
+            splitter = participants
+                .iter()
+                .position(|group| !group.has_reached_quota() && is_available(group));
+        }
+        let splitter = splitter
+            .or_else(|| participants.iter().position(&is_available))
+            .unwrap_or(participants.len());
+        let mut skipped = Vec::new();
+        for group in participants.iter().take(splitter) {
+            let obstacle = get_obstacle(group);
+            if let Some(JsonSkipReason::Belegt(course_type)) = obstacle {
+                let address = group as *const Group;
+                if course_type != course.course_type
+                    && course.kind != CourseKind::Cancelled
+                    && !conflicts.contains(&address)
+                {
+                    group.conflicts.set(group.conflicts.get() + 1);
+                    conflicts.push(address);
+                }
+            }
+            if context.explain {
+                skipped.push(JsonSkip {
+                    studenten: group.get_numbers(),
+                    grund: obstacle.unwrap_or(if context.quota_priority && group.has_reached_quota() {
+                        JsonSkipReason::KontingentErreicht
+                    } else {
+                        JsonSkipReason::Vorrang
+                    }),
+                });
+            }
+        }
+        let mut rest = participants.split_off(splitter); // the due to occupation skipped part
+        let group = match rest.pop_front() {
+            // get relevant group
+            Some(group) => group,
+            None => {
+                record(seat, queue, skipped, None, false);
+                continue;
+            }
+        };
+        rest.push_back(group); // move the relevant group to the back
+        participants.append(&mut rest); // reunite
+        if let Some(group) = participants.back() {
+            // get relevant group (old group was consumed by pushing)
+            take_seat(course, group, context);
+            record(seat, queue, skipped, Some(group), false);
+        }
+    }
+    Ok(space_count)
+}
+
+/// Takes a list of Groups and distributes them among the courses of the given type.
+/// The distributed groups will be moved to the end of the given group list, so
+/// that the next time, they will be distributed with the least priority
+fn distribute_courses(
+    course_type: CourseType,
+    day_index: usize,
+    day: &Day,
+    context: &Context,
+    participants: &mut LinkedList<Group>,
+) -> Result<(), String> {
+    let courses = day.courses.borrow();
+    for course in courses
+        .iter()
+        .filter(|course| course.course_type == course_type)
+    {
+        distribute_course(
+            course,
+            day_index,
+            day,
+            context,
+            participants,
+            get_seat_count(course_type),
+        )?;
+    }
+    Ok(())
+}
+
+/// Distributes the Zahnersatz courses among the cohorts.
+/// The seats of a course are shared equally between all cohorts that are allowed to use
+/// its slot, left over seats go to the cohorts listed first.
+fn distribute_zahnersatz(
+    day_index: usize,
+    day: &Day,
+    context: &Context,
+    cohorts: &mut [Cohort],
+) -> Result<(), String> {
+    let courses = day.courses.borrow();
+    for course in courses
+        .iter()
+        .filter(|course| course.course_type == CourseType::Zahnersatz)
+    {
+        for (index, seats) in get_cohort_seats(cohorts, day_index, course) {
+            distribute_course(
+                course,
+                day_index,
+                day,
+                context,
+                &mut cohorts[index].groups,
+                seats,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// distributes all courses of the day, in the order of the context
+fn distribute_day(day_index: usize, day: &Day, context: &Context, rotations: &mut Rotations) -> Result<(), String> {
+    for course_type in context.course_order.iter() {
+        match *course_type {
+            CourseType::Curriculum => {
+                distribute_courses(CourseType::Curriculum, day_index, day, context, &mut rotations.curriculum)?
+            }
+            CourseType::Exkurs => {
+                distribute_courses(CourseType::Exkurs, day_index, day, context, &mut rotations.exkurs)?
+            }
+            CourseType::Zahnersatz => {
+                distribute_zahnersatz(day_index, day, context, &mut rotations.zahnersatz)?
+            }
+            CourseType::Zahnerhalt => {
+                distribute_courses(CourseType::Zahnerhalt, day_index, day, context, &mut rotations.zahnerhalt)?
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_course<T: Write>(
+    file: &mut T,
+    week: &Week,
+    course_type: CourseType,
+    beginning: u8,
+    width: usize,
+) -> Result<(), io::Error> {
+    write!(file, "{} {}   ", course_type, beginning)?;
+    for day_index in 0..5 {
+        let current_day = &week.days[day_index];
+
+        if let Some(course) =
+            current_day.courses.borrow().iter().find(|course| {
+                course.course_type == course_type && course.beginning == beginning
+            }) {
+            write!(file, "{}", CoursePrinter(course, width))?;
+        } else {
+            write!(file, "{}", StudentPrinter(&LinkedList::new(), width))?;
+        }
+    }
+    writeln!(file)?;
+    Ok(())
+}
+
+/// the width of a day column, every column has room for at least 14 students
+pub fn get_column_width(weeks: &[Week]) -> usize {
+    weeks
+        .iter()
+        .flat_map(|week| week.days.iter())
+        .flat_map(|day| {
+            day.courses
+                .borrow()
+                .iter()
+                .map(|course| course.participants.borrow().len())
+                .collect::<Vec<usize>>()
+        })
+        .fold(14, usize::max)
+}
+
+/// writes the courses of a week with a column for every day
+pub fn print_week<T: Write>(file: &mut T, week: &Week, width: usize) -> Result<(), io::Error> {
+    writeln!(file, "KW {}", week.number)?;
+    let mut header = format!("{:15}", "");
+    for day_name in DAY_NAMES.iter() {
+        header.push_str(&format!("{:1$}", day_name, width * 3));
+    }
+    writeln!(file, "{}", header.trim_end())?;
+    print_course(file, week, CourseType::Curriculum, 7, width)?;
+    print_course(file, week, CourseType::Exkurs, 7, width)?;
+    print_course(file, week, CourseType::Zahnerhalt, 7, width)?;
+    print_course(file, week, CourseType::Zahnerhalt, 16, width)?;
+    print_course(file, week, CourseType::Zahnersatz, 7, width)?;
+    print_course(file, week, CourseType::Zahnersatz, 16, width)?;
+    Ok(())
+}
+
+///takes in the data, formats it so that it is humanly readable and writes it to the given Writer
+pub fn generate_output<T: Write>(
+    file: &mut T,
+    weeks: &[Week],
+    changes: &[HolidayChange],
+    conflicts: &[u64; 4],
+    statistics: &[StudentStatistics],
+) -> Result<(), io::Error> {
+    let has_operators = weeks.iter().any(|week| {
+        week.days.iter().any(|day| {
+            day.courses
+                .borrow()
+                .iter()
+                .any(|course| !course.operators.borrow().is_empty())
+        })
+    });
+    let width = get_column_width(weeks);
+    if has_operators {
+        writeln!(file, "Zahnersatz: Behandler sind mit * markiert, ihre Partner assistieren")?;
+        writeln!(file)?;
+    }
+    for current_week in weeks {
+        print_week(file, current_week, width)?;
+        writeln!(file)?;
+        writeln!(file)?;
+    }
+    if !changes.is_empty() {
+        writeln!(file, "Feiertage")?;
+        for change in changes.iter() {
+            writeln!(file, "{}", change)?;
+        }
+        writeln!(file)?;
+    }
+    if conflicts.iter().any(|count| *count > 0) {
+        print_conflicts(file, conflicts)?;
+        writeln!(file)?;
+    }
+    print_statistics(file, statistics)?;
+    Ok(())
+}
+
+/// the options of a distribution that are given on the command line
+#[derive(Debug, Default)]
+pub struct Options {
+    pub from_week: Option<u64>, // weeks before this one are taken from the previous distribution
+    pub previous: Option<JsonSchedule>,
+    pub previous_semester: Option<JsonSchedule>, // the rotations start where it ended
+    pub explain: bool,                           // every seat decision is recorded
+    pub quiet: bool,                             // the progress is not printed
+}
+
+/// everything that results from a distribution
+pub struct Distribution {
+    pub weeks: Vec<Week>,
+    pub holiday_changes: Vec<HolidayChange>,
+    pub students: LinkedList<Rc<Student>>,
+    pub rotations: Rotations,
+    pub schedule: JsonSchedule,
+    pub decisions: Vec<JsonDecision>,
+}
+
+fn get_context(input: &JsonData, week_number: u64, explain: bool) -> Context {
+    Context {
+        week_number,
+        makeup_priority: match input.nachholen {
+            Some(ref makeup) => makeup.art == JsonMakeUpMethod::Vorrang,
+            None => false,
+        },
+        // only needed if the students differ in their quotas
+        quota_priority: input
+            .studenten
+            .iter()
+            .any(|student| !student.zusaetzlich.is_empty() || !student.absolviert.is_empty()),
+        course_order: get_course_order(input, week_number),
+        explain,
+        decisions: RefCell::new(Vec::new()),
+    }
+}
+
+/// distributes the students of the input among all courses of the semester
+pub fn distribute(input: &JsonData, options: &Options) -> Distribution {
+    let holiday_changes = get_holiday_changes(input);
+    let weeks = get_weeks(input, &holiday_changes);
+    progress!(options, "---parsed weeks---");
+    //println!("{:#?}", weeks);
+    let students = get_students(input);
+    progress!(options, "---parsed students---");
+    for student in students.iter() {
+        progress!(options, "{}", student)
+    }
+    let mut rotations = get_rotations(input, &students);
+    progress!(options, "---parsed curriculum groups---");
+    for group in rotations.curriculum.iter() {
+        progress!(options, "{}", group)
+    }
+    progress!(options, "---parsed exkurs groups---");
+    for group in rotations.exkurs.iter() {
+        progress!(options, "{}", group)
+    }
+    progress!(options, "---parsed Zahnersatz groups---");
+    for (index, cohort) in rotations.zahnersatz.iter().enumerate() {
+        progress!(options, "cohort {}:", index + 1);
+        for group in cohort.groups.iter() {
+            progress!(options, "{}", group)
+        }
+    }
+    progress!(options, "---parsed Zahnerhalt groups---");
+    if let Some(seed) = input.rotationsStartwert {
+        rotations.shuffle(seed);
+        progress!(options, "---shuffled rotations---");
+    }
+    if let Some(ref previous_semester) = options.previous_semester {
+        restore_rotations(&previous_semester.rotationen, &mut rotations, false);
+        progress!(options, "---continued rotations of the previous semester---");
+    }
+    if let Err(errors) = apply_pins(input, &weeks, &students, &rotations) {
+        for error in errors.iter() {
+            println!("{}", error);
+        }
+        panic!("Failed to apply the fixierungen");
+    }
+    progress!(options, "---applied pins---");
+    let mut week_states = Vec::new();
+    let mut decisions = Vec::new();
+    for current_week in weeks.iter() {
+        if let (Some(from_week), Some(previous)) = (options.from_week, options.previous.as_ref()) {
+            let previous_week = previous
+                .wochen
+                .iter()
+                .find(|week| week.nummer == current_week.number);
+            if current_week.number < from_week {
+                progress!(options, "---keep week {}---", current_week.number);
+                match previous_week {
+                    Some(previous_week) => {
+                        restore_week(current_week, previous_week, &students);
+                        week_states.push(previous_week.rotationen.clone());
+                    }
+                    None => {
+                        println!("Warning: KW {} is not part of the previous distribution", current_week.number);
+                        week_states.push(get_rotation_states(&rotations));
+                    }
+                }
+                continue;
+            }
+            if current_week.number == from_week {
+                // continue the rotations where the previous distribution was in this week
+                match previous_week {
+                    Some(previous_week) => {
+                        restore_rotations(&previous_week.rotationen, &mut rotations, true)
+                    }
+                    None => restore_rotations(&previous.rotationen, &mut rotations, true),
+                }
+            }
+        }
+        week_states.push(get_rotation_states(&rotations));
+        progress!(options, "---process week {}---", current_week.number);
+        let context = get_context(input, current_week.number, options.explain);
+        for day_index in 0..5 {
+            progress!(options, "---process day {}---", day_index);
+            // the groups that would have been next lose the cancelled sessions, they are
+            // only made up with nachholen
+            let cancelled = get_cancelled_courses(&holiday_changes, current_week.number, day_index);
+            if let Err(error) = distribute_day(day_index, &cancelled, &context, &mut rotations) {
+                panic!("{}", error);
+            }
+            let current_day = &current_week.days[day_index];
+            if let Err(error) = distribute_day(day_index, current_day, &context, &mut rotations) {
+                panic!("{}", error);
+            }
+        }
+        decisions.append(&mut context.decisions.borrow_mut());
+    }
+    let schedule = get_schedule(&weeks, week_states, get_rotation_states(&rotations));
+    Distribution {
+        weeks,
+        holiday_changes,
+        students,
+        rotations,
+        schedule,
+        decisions,
+    }
+}
+
+/// writes the distribution together with its statistics to std out and "Alban says.txt"
+pub fn write_output(
+    input: &JsonData,
+    weeks: &[Week],
+    holiday_changes: &[HolidayChange],
+    students: &LinkedList<Rc<Student>>,
+    rotations: &Rotations,
+) {
+    let mut file = match File::create("Alban says.txt") {
+        Err(why) => panic!("couldn't create {}: {}", "Alban says.txt", why),
+        Ok(file) => file,
+    };
+    let statistics = get_statistics(input, weeks, students, rotations);
+    let conflicts = get_conflicts(rotations);
+    let _ = generate_output(
+        &mut std::io::stdout(),
+        weeks,
+        holiday_changes,
+        &conflicts,
+        &statistics,
+    );
+    if let Err(err) = generate_output(&mut file, weeks, holiday_changes, &conflicts, &statistics) {
+        println!("Unable to write output to file: {}", err);
+    }
+}
+
+/// Writes the distribution to "Alban says.txt" and "Alban says.json" without printing it,
+/// e.g. for the interfaces. Returns the statistics of the students.
+pub fn write_files(input: &JsonData, distribution: &Distribution) -> Result<Vec<StudentStatistics>, io::Error> {
+    let statistics = get_statistics(
+        input,
+        &distribution.weeks,
+        &distribution.students,
+        &distribution.rotations,
+    );
+    let conflicts = get_conflicts(&distribution.rotations);
+    let mut file = File::create("Alban says.txt")?;
+    generate_output(
+        &mut file,
+        &distribution.weeks,
+        &distribution.holiday_changes,
+        &conflicts,
+        &statistics,
+    )?;
+    write_schedule("Alban says.json", &distribution.schedule);
+    Ok(statistics)
+}
+
+/// Builds the weeks and rotations of a distribution that was read from a file. The lost
+/// and made up sessions are taken from the rotation states of the export, if there are any.
+pub fn restore_distribution(input: &JsonData, schedule: JsonSchedule) -> Distribution {
+    let holiday_changes = get_holiday_changes(input);
+    let weeks = get_weeks(input, &holiday_changes);
+    let students = get_students(input);
+    let mut rotations = get_rotations(input, &students);
+    restore_rotations(&schedule.rotationen, &mut rotations, true);
+    for week in weeks.iter() {
+        if let Some(scheduled) = schedule.wochen.iter().find(|scheduled| scheduled.nummer == week.number) {
+            restore_week(week, scheduled, &students);
+        }
+    }
+    Distribution {
+        weeks,
+        holiday_changes,
+        students,
+        rotations,
+        schedule,
+        decisions: Vec::new(),
+    }
+}
+
+/// The groups of the rotation that take part in the course take their seats in the order
+/// of their students in the course, the way distribute_course seated them.
+fn replay_course(course: &Course, context: &Context, participants: &mut LinkedList<Group>) {
+    let attendees = course.participants.borrow();
+    let mut seated = Vec::new();
+    let mut rest = LinkedList::new();
+    while let Some(group) = participants.pop_front() {
+        let seat = group
+            .get_enrolled(context.week_number)
+            .iter()
+            .filter_map(|student| attendees.iter().position(|attendee| attendee == student))
+            .min();
+        match seat {
+            Some(seat) => seated.push((seat, group)),
+            None => rest.push_back(group),
+        }
+    }
+    seated.sort_by_key(|&(seat, _)| seat);
+    for (_, group) in seated {
+        count_session(course, &group, context);
+        rest.push_back(group);
+    }
+    *participants = rest;
+}
+
+/// Recomputes the rotation states of a schedule whose courses were changed by hand (alban
+/// move and swap). Starting with the state of the first week, the groups take the seats they
+/// have in the schedule and lose the cancelled courses like in distribute, so that a later
+/// --previous or --from-week continues from the changed courses. The conflicts can not be
+/// told from the schedule, they stay as they were.
+pub fn update_rotation_states(input: &JsonData, schedule: &mut JsonSchedule) {
+    let holiday_changes = get_holiday_changes(input);
+    let weeks = get_weeks(input, &holiday_changes);
+    let students = get_students(input);
+    let mut rotations = get_rotations(input, &students);
+    if let Some(first) = schedule.wochen.first() {
+        restore_rotations(&first.rotationen, &mut rotations, true);
+    }
+    for week in weeks.iter() {
+        let index = match schedule.wochen.iter().position(|scheduled| scheduled.nummer == week.number) {
+            Some(index) => index,
+            None => continue,
+        };
+        restore_week(week, &schedule.wochen[index], &students);
+        let states = get_rotation_states(&rotations);
+        keep_conflicts(&mut schedule.wochen[index].rotationen, states);
+        let context = get_context(input, week.number, false);
+        for day_index in 0..5 {
+            let cancelled = get_cancelled_courses(&holiday_changes, week.number, day_index);
+            if let Err(error) = distribute_day(day_index, &cancelled, &context, &mut rotations) {
+                panic!("{}", error);
+            }
+            for course in week.days[day_index].courses.borrow().iter() {
+                match course.course_type {
+                    CourseType::Curriculum => replay_course(course, &context, &mut rotations.curriculum),
+                    CourseType::Exkurs => replay_course(course, &context, &mut rotations.exkurs),
+                    CourseType::Zahnerhalt => replay_course(course, &context, &mut rotations.zahnerhalt),
+                    CourseType::Zahnersatz => {
+                        for cohort in rotations.zahnersatz.iter_mut() {
+                            replay_course(course, &context, &mut cohort.groups);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let states = get_rotation_states(&rotations);
+    keep_conflicts(&mut schedule.rotationen, states);
+}
+
+/// replaces the states, the groups keep the conflicts of their previous state
+fn keep_conflicts(previous: &mut Vec<JsonRotation>, mut states: Vec<JsonRotation>) {
+    for state in states.iter_mut() {
+        let groups = match previous.iter().find(|rotation| rotation.name == state.name) {
+            Some(rotation) => &rotation.gruppen,
+            None => continue,
+        };
+        for group in state.gruppen.iter_mut() {
+            if let Some(before) = groups.iter().find(|before| before.studenten == group.studenten) {
+                group.konflikte = before.konflikte;
+            }
+        }
+    }
+    *previous = states;
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::*;
+    use edit::*;
+    use fixtures::{distribute_quietly, get_input, get_input_value};
+    use parser::parse_value;
+
+    #[test]
+    fn records_the_sessions_lost_to_holidays_without_nachholen() {
+        let data = get_input(serde_json::json!({}));
+        assert!(data.nachholen.is_none());
+        let distribution = distribute_quietly(&data, Options::default());
+        let statistics = get_statistics(
+            &data,
+            &distribution.weeks,
+            &distribution.students,
+            &distribution.rotations,
+        );
+        assert!(statistics.iter().all(|entry| entry.lost > 0 && entry.made_up == 0));
+    }
+
+    #[test]
+    fn recomputes_the_rotation_states_of_an_unchanged_schedule() {
+        let mut input = get_input_value();
+        for changes in [
+            serde_json::json!({}),
+            serde_json::json!({
+                "nachholen": {"art": "vorrang"},
+                "kontingente": {"Zahnerhalt": 20},
+                "studenten": [{"nummer": 3, "absolviert": {"Zahnerhalt": 15}}],
+                "fixierungen": [{"woche": 15, "tag": 2, "kurs": "Exkurs", "beginn": 7, "gruppe": 4}]
+            }),
+        ] {
+            for (key, value) in changes.as_object().unwrap() {
+                input[key] = value.clone();
+            }
+            let data = parse_value(&input).unwrap();
+            let schedule = distribute_quietly(&data, Options::default()).schedule;
+            let mut updated = schedule.clone();
+            update_rotation_states(&data, &mut updated);
+            assert_eq!(
+                serde_json::to_value(&updated).unwrap(),
+                serde_json::to_value(&schedule).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn recomputes_the_rotation_states_after_a_move() {
+        let data = get_input(serde_json::json!({}));
+        let mut schedule = distribute_quietly(&data, Options::default()).schedule;
+        let get_sessions = |states: &[JsonRotation], student: u64| {
+            states
+                .iter()
+                .filter(|state| state.name == "Zahnerhalt")
+                .flat_map(|state| state.gruppen.iter())
+                .find(|group| group.studenten.contains(&student))
+                .map(|group| group.sitzungen)
+                .unwrap()
+        };
+        // the first Zahnerhalt student of KW 14 visits the course of KW 16 instead
+        let (from, student) = {
+            let week = schedule.wochen.iter().find(|week| week.nummer == 14).unwrap();
+            let course = week.kurse.iter().find(|course| course.kurs == CourseType::Zahnerhalt).unwrap();
+            (course.clone(), course.teilnehmer[0])
+        };
+        let placement = Placement {
+            student,
+            slot: JsonCourseSlot {
+                woche: 14,
+                tag: from.tag,
+                kurs: from.kurs,
+                beginn: from.beginn,
+            },
+        };
+        let to = JsonCourseSlot {
+            woche: 16,
+            ..placement.slot
+        };
+        let edit = EditOptions {
+            single: true,
+            force: true,
+        };
+        move_students(&data, &mut schedule, &placement, &to, &edit).unwrap();
+        let before = get_sessions(&schedule.wochen[3].rotationen, student);
+        update_rotation_states(&data, &mut schedule);
+        // KW 15 starts with one session less, the end of the semester has as many as before
+        assert_eq!(schedule.wochen[3].nummer, 15);
+        assert_eq!(get_sessions(&schedule.wochen[3].rotationen, student) + 1, before);
+        let distributed = distribute_quietly(&data, Options::default()).schedule;
+        assert_eq!(
+            get_sessions(&schedule.rotationen, student),
+            get_sessions(&distributed.rotationen, student)
+        );
+    }
+}
@@ -0,0 +1,305 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::LinkedList;
+
+use types::*;
+use parser::*;
+use verify::verify;
+
+/// a student in one of its courses
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    pub student: u64,
+    pub slot: JsonCourseSlot,
+}
+
+/// how a manual change is carried out
+#[derive(Debug, Default, Clone, Copy)]
+pub struct EditOptions {
+    pub single: bool, // only the student is changed instead of its whole group
+    pub force: bool,  // the change is made even if it violates the constraints
+}
+
+fn find_course_mut<'a>(
+    schedule: &'a mut JsonSchedule,
+    slot: &JsonCourseSlot,
+) -> Option<&'a mut JsonScheduleCourse> {
+    schedule
+        .wochen
+        .iter_mut()
+        .find(|week| week.nummer == slot.woche)?
+        .kurse
+        .iter_mut()
+        .find(|course| {
+            course.tag == slot.tag && course.kurs == slot.kurs && course.beginn == slot.beginn
+        })
+}
+
+/// The students that are changed together with the student of the placement: the members
+/// of its group that take part in the course, or only the student itself.
+fn get_moved(
+    parsed_data: &JsonData,
+    schedule: &JsonSchedule,
+    placement: &Placement,
+    options: &EditOptions,
+) -> Result<Vec<u64>, String> {
+    let slot = &placement.slot;
+    let participants = match schedule
+        .wochen
+        .iter()
+        .filter(|week| week.nummer == slot.woche)
+        .flat_map(|week| week.kurse.iter())
+        .find(|course| {
+            course.tag == slot.tag && course.kurs == slot.kurs && course.beginn == slot.beginn
+        }) {
+        Some(course) => &course.teilnehmer,
+        None => return Err(format!("{}: there is no such course", slot)),
+    };
+    if !participants.contains(&placement.student) {
+        return Err(format!(
+            "{}: student {} does not take part",
+            placement.slot, placement.student
+        ));
+    }
+    if options.single {
+        return Ok(vec![placement.student]);
+    }
+    let students = get_students(parsed_data);
+    let groups: LinkedList<Group> = match placement.slot.kurs {
+        CourseType::Curriculum => get_curriculum_groups(parsed_data, &students),
+        CourseType::Exkurs => get_exkurs_groups(parsed_data, &students),
+        CourseType::Zahnerhalt => get_zahnerhalt_groups(&students),
+        CourseType::Zahnersatz => get_zahnersatz_cohorts(parsed_data, &students)
+            .into_iter()
+            .flat_map(|cohort| cohort.groups.into_iter())
+            .collect(),
+    };
+    let members = groups
+        .iter()
+        .map(Group::get_numbers)
+        .find(|members| members.contains(&placement.student))
+        .unwrap_or_else(|| vec![placement.student]);
+    Ok(members
+        .into_iter()
+        .filter(|member| participants.contains(member))
+        .collect())
+}
+
+/// moves the students (and their operator marks) from one course to another
+fn transfer(
+    schedule: &mut JsonSchedule,
+    students: &[u64],
+    from: &JsonCourseSlot,
+    to: &JsonCourseSlot,
+) -> Result<(), String> {
+    let operators: Vec<u64> = match find_course_mut(schedule, from) {
+        Some(course) => {
+            course.teilnehmer.retain(|student| !students.contains(student));
+            let operators = course
+                .behandler
+                .iter()
+                .filter(|student| students.contains(student))
+                .cloned()
+                .collect();
+            course.behandler.retain(|student| !students.contains(student));
+            operators
+        }
+        None => return Err(format!("{}: there is no such course", from)),
+    };
+    if find_course_mut(schedule, to).is_none() {
+        // empty courses may be missing in hand-written schedules
+        match schedule.wochen.iter_mut().find(|week| week.nummer == to.woche) {
+            Some(week) => week.kurse.push(JsonScheduleCourse {
+                tag: to.tag,
+                kurs: to.kurs,
+                beginn: to.beginn,
+                teilnehmer: Vec::new(),
+                behandler: Vec::new(),
+            }),
+            None => return Err(format!("KW {}: the week is not part of the schedule", to.woche)),
+        }
+    }
+    if let Some(course) = find_course_mut(schedule, to) {
+        course.teilnehmer.extend(students.iter().cloned());
+        course.behandler.extend(operators);
+    }
+    Ok(())
+}
+
+/// Checks the changed schedule like verify does. Only violations that did not exist before
+/// count, so that schedules that already have violations can still be changed.
+/// Without force the schedule is only replaced if there are no new violations.
+fn commit(
+    parsed_data: &JsonData,
+    schedule: &mut JsonSchedule,
+    changed: JsonSchedule,
+    options: &EditOptions,
+) -> Result<Vec<String>, Vec<String>> {
+    let before = verify(parsed_data, schedule);
+    let violations: Vec<String> = verify(parsed_data, &changed)
+        .into_iter()
+        .filter(|violation| !before.contains(violation))
+        .collect();
+    if !violations.is_empty() && !options.force {
+        return Err(violations);
+    }
+    *schedule = changed;
+    Ok(violations)
+}
+
+/// Moves the group of the student (or only the student) to another course of the same type.
+/// Returns the violations the move causes, as warnings if it was forced and as error
+/// if the schedule stays unchanged.
+pub fn move_students(
+    parsed_data: &JsonData,
+    schedule: &mut JsonSchedule,
+    placement: &Placement,
+    to: &JsonCourseSlot,
+    options: &EditOptions,
+) -> Result<Vec<String>, Vec<String>> {
+    if placement.slot.kurs != to.kurs {
+        return Err(vec![format!("{}: only courses of the same type can be exchanged", to)]);
+    }
+    let moved = get_moved(parsed_data, schedule, placement, options).map_err(|error| vec![error])?;
+    let mut changed = schedule.clone();
+    transfer(&mut changed, &moved, &placement.slot, to).map_err(|error| vec![error])?;
+    commit(parsed_data, schedule, changed, options)
+}
+
+/// Exchanges the groups of two students (or only the students) between their courses.
+/// Returns the violations like move_students.
+pub fn swap_students(
+    parsed_data: &JsonData,
+    schedule: &mut JsonSchedule,
+    first: &Placement,
+    second: &Placement,
+    options: &EditOptions,
+) -> Result<Vec<String>, Vec<String>> {
+    if first.slot.kurs != second.slot.kurs {
+        return Err(vec![format!(
+            "{}: only courses of the same type can be exchanged",
+            second.slot
+        )]);
+    }
+    let first_moved = get_moved(parsed_data, schedule, first, options).map_err(|error| vec![error])?;
+    let second_moved = get_moved(parsed_data, schedule, second, options).map_err(|error| vec![error])?;
+    let mut changed = schedule.clone();
+    transfer(&mut changed, &first_moved, &first.slot, &second.slot).map_err(|error| vec![error])?;
+    transfer(&mut changed, &second_moved, &second.slot, &first.slot).map_err(|error| vec![error])?;
+    commit(parsed_data, schedule, changed, options)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::*;
+    use distribution::Options;
+    use fixtures::{distribute_quietly, get_input};
+
+    fn get_slot(day: u64, course_type: CourseType, beginning: u8) -> JsonCourseSlot {
+        JsonCourseSlot {
+            woche: 14,
+            tag: day,
+            kurs: course_type,
+            beginn: beginning,
+        }
+    }
+
+    fn get_participants(schedule: &JsonSchedule, slot: &JsonCourseSlot) -> Vec<u64> {
+        schedule.wochen[2]
+            .kurse
+            .iter()
+            .find(|course| course.tag == slot.tag && course.kurs == slot.kurs && course.beginn == slot.beginn)
+            .map(|course| course.teilnehmer.clone())
+            .unwrap()
+    }
+
+    #[test]
+    fn refuses_moves_that_cause_violations() {
+        let data = get_input(serde_json::json!({}));
+        let mut schedule = distribute_quietly(&data, Options::default()).schedule;
+        let distributed = serde_json::to_value(&schedule).unwrap();
+        // the course on wednesday already has 11 participants and 11 has exkurs at the time
+        let placement = Placement {
+            student: 11,
+            slot: get_slot(1, CourseType::Zahnerhalt, 7),
+        };
+        let to = get_slot(3, CourseType::Zahnerhalt, 7);
+        let single = EditOptions {
+            single: true,
+            force: false,
+        };
+        assert_eq!(
+            move_students(&data, &mut schedule, &placement, &to, &single),
+            Err(vec![
+                "KW 14 Mittwoch   Zahnerhalt  7: 12 groups exceed the 11 seats".to_string(),
+                "KW 14 Mittwoch   Zahnerhalt  7: student 11 is in Exkurs at the same time".to_string(),
+            ])
+        );
+        let to = get_slot(3, CourseType::Curriculum, 7);
+        assert_eq!(
+            move_students(&data, &mut schedule, &placement, &to, &single),
+            Err(vec!["KW 14 Mittwoch   Curriculum  7: only courses of the same type can be exchanged".to_string()])
+        );
+        assert_eq!(serde_json::to_value(&schedule).unwrap(), distributed);
+    }
+
+    #[test]
+    fn forces_the_move_of_a_group() {
+        let data = get_input(serde_json::json!({}));
+        let mut schedule = distribute_quietly(&data, Options::default()).schedule;
+        // 9 and 19 are an exkurs group, 10 and 12 have tuesday
+        let placement = Placement {
+            student: 9,
+            slot: get_slot(1, CourseType::Exkurs, 7),
+        };
+        let to = get_slot(2, CourseType::Exkurs, 7);
+        let force = EditOptions {
+            single: false,
+            force: true,
+        };
+        let warnings = move_students(&data, &mut schedule, &placement, &to, &force).unwrap();
+        assert!(warnings.contains(&"KW 14 Dienstag   Exkurs      7: 2 groups exceed the 1 seats".to_string()));
+        assert!(get_participants(&schedule, &placement.slot).is_empty());
+        assert_eq!(get_participants(&schedule, &to), vec![10, 12, 9, 19]);
+    }
+
+    #[test]
+    fn swaps_two_students() {
+        let data = get_input(serde_json::json!({}));
+        let mut schedule = distribute_quietly(&data, Options::default()).schedule;
+        let first = Placement {
+            student: 1,
+            slot: get_slot(3, CourseType::Zahnerhalt, 7),
+        };
+        let second = Placement {
+            student: 8,
+            slot: get_slot(3, CourseType::Zahnerhalt, 16),
+        };
+        let single = EditOptions {
+            single: true,
+            force: false,
+        };
+        assert_eq!(swap_students(&data, &mut schedule, &first, &second, &single), Ok(Vec::new()));
+        let morning = get_participants(&schedule, &first.slot);
+        let afternoon = get_participants(&schedule, &second.slot);
+        assert!(morning.contains(&8) && !morning.contains(&1));
+        assert!(afternoon.contains(&1) && !afternoon.contains(&8));
+        assert_eq!((morning.len(), afternoon.len()), (11, 11));
+    }
+}
@@ -0,0 +1,207 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+extern crate serde_json;
+
+use std::fs::File;
+
+use types::*;
+
+/// writes the recorded seat decisions as json
+pub fn write_decisions(path: &str, decisions: &[JsonDecision]) {
+    match File::create(path) {
+        Err(error) => println!("couldn't create {}: {}", path, error),
+        Ok(file) => {
+            if let Err(error) = serde_json::to_writer_pretty(file, decisions) {
+                println!("Unable to write decisions to file: {}", error);
+            }
+        }
+    }
+}
+
+/// limits the explanations to some of the courses
+#[derive(Debug, Default)]
+pub struct ExplainFilter {
+    pub week: Option<u64>,
+    pub day: Option<u64>, // 1-5
+    pub course_type: Option<CourseType>,
+}
+
+impl ExplainFilter {
+    fn matches(&self, decision: &JsonDecision) -> bool {
+        self.week.is_none_or(|week| week == decision.woche)
+            && self.day.is_none_or(|day| day == decision.tag)
+            && self.course_type.is_none_or(|course_type| course_type == decision.kurs)
+    }
+}
+
+/// explains the decisions of a single course for the student
+fn explain_course(decisions: &[&JsonDecision], student: u64) -> String {
+    let first = decisions[0];
+    let mut explanation = format!(
+        "KW {} {:<10} {} {:>2}: ",
+        first.woche,
+        DAY_NAMES[first.tag as usize - 1],
+        first.kurs,
+        first.beginn
+    );
+    match first.art {
+        CourseKind::Regular => {}
+        CourseKind::MakeUp => explanation.push_str("(Nachholtermin) "),
+        CourseKind::Cancelled => explanation.push_str("(ausgefallen) "),
+    }
+    let has_student = |numbers: &Vec<u64>| numbers.contains(&student);
+    if let Some(decision) = decisions
+        .iter()
+        .find(|decision| decision.vergeben.as_ref().is_some_and(&has_student))
+    {
+        explanation.push_str(&format!("Platz {} erhalten", decision.platz));
+        if decision.fixiert {
+            explanation.push_str(" (fixiert)");
+        }
+    } else if let Some(skip) = decisions
+        .iter()
+        .flat_map(|decision| decision.uebersprungen.iter())
+        .find(|skip| has_student(&skip.studenten))
+    {
+        explanation.push_str(&format!("übersprungen, {}", skip.grund));
+    } else {
+        // the seats were gone before it was the turn of the student
+        let position = first
+            .warteschlange
+            .iter()
+            .position(&has_student)
+            .unwrap_or(0);
+        let given = decisions
+            .iter()
+            .filter(|decision| decision.vergeben.is_some())
+            .count();
+        explanation.push_str(&format!(
+            "nicht an der Reihe, Position {} von {} in der Rotation bei {} vergebenen Plätzen",
+            position + 1,
+            first.warteschlange.len(),
+            given
+        ));
+    }
+    explanation
+}
+
+/// Explains for every course of the filter the student could have visited, whether the
+/// student got a seat and why not. Courses are told apart by their first decision.
+pub fn explain_student(
+    decisions: &[JsonDecision],
+    student: u64,
+    filter: &ExplainFilter,
+) -> Vec<String> {
+    let mut courses: Vec<Vec<&JsonDecision>> = Vec::new();
+    for decision in decisions.iter().filter(|decision| filter.matches(decision)) {
+        let same_course = courses.last().is_some_and(|course| {
+            let first = course[0];
+            first.woche == decision.woche
+                && first.tag == decision.tag
+                && first.kurs == decision.kurs
+                && first.beginn == decision.beginn
+                && first.art == decision.art
+        });
+        if !same_course {
+            courses.push(Vec::new());
+        }
+        if let Some(course) = courses.last_mut() {
+            course.push(decision);
+        }
+    }
+    courses
+        .into_iter()
+        .map(|course| {
+            // only the decisions of the rotation the student belongs to
+            course
+                .into_iter()
+                .filter(|decision| decision.warteschlange.iter().any(|group| group.contains(&student)))
+                .collect::<Vec<&JsonDecision>>()
+        })
+        .filter(|course| !course.is_empty())
+        .map(|course| explain_course(&course, student))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::*;
+    use distribution::Options;
+    use fixtures::{distribute_quietly, get_input};
+    use schedule::get_timetable;
+
+    fn get_decisions() -> (JsonSchedule, Vec<JsonDecision>) {
+        let data = get_input(serde_json::json!({}));
+        let options = Options {
+            explain: true,
+            ..Options::default()
+        };
+        let distribution = distribute_quietly(&data, options);
+        (distribution.schedule, distribution.decisions)
+    }
+
+    #[test]
+    fn explains_the_seat_of_every_course_of_the_timetable() {
+        let (schedule, decisions) = get_decisions();
+        let explanations = explain_student(&decisions, 1, &ExplainFilter::default());
+        let seats: Vec<&String> = explanations
+            .iter()
+            .filter(|explanation| explanation.contains("erhalten") && !explanation.contains("(ausgefallen)"))
+            .collect();
+        let timetable = get_timetable(&schedule, 1);
+        assert_eq!(seats.len(), timetable.termine.len());
+        for (explanation, slot) in seats.iter().zip(timetable.termine.iter()) {
+            let course = format!(
+                "KW {} {:<10} {} {:>2}: Platz",
+                slot.woche,
+                DAY_NAMES[slot.tag as usize - 1],
+                slot.kurs,
+                slot.beginn
+            );
+            assert!(explanation.starts_with(&course), "{} is not {}", explanation, course);
+        }
+        // the holidays of KW 12 cost the courses of monday and tuesday
+        assert!(explanations
+            .iter()
+            .any(|explanation| explanation.starts_with("KW 12 Montag") && explanation.contains("(ausgefallen)")));
+    }
+
+    #[test]
+    fn explains_why_a_student_was_skipped() {
+        let (_, decisions) = get_decisions();
+        let filter = ExplainFilter {
+            week: Some(14),
+            day: Some(1),
+            course_type: Some(CourseType::Zahnerhalt),
+        };
+        // the curriculum group of student 1 has its course on monday
+        assert_eq!(
+            explain_student(&decisions, 1, &filter),
+            vec!["KW 14 Montag     Zahnerhalt  7: übersprungen, gleichzeitig in Curriculum"]
+        );
+        let filter = ExplainFilter {
+            week: Some(14),
+            day: Some(3),
+            course_type: Some(CourseType::Zahnerhalt),
+        };
+        let explanations = explain_student(&decisions, 1, &filter);
+        assert_eq!(explanations.len(), 2);
+        assert!(explanations[1].contains("nicht an der Reihe, Position"), "{}", explanations[1]);
+    }
+}
@@ -0,0 +1,52 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+// the sample input of the repository, shared by the unit tests
+
+extern crate serde_json;
+
+use self::serde_json::Value;
+
+use types::*;
+use parser::parse_value;
+use distribution::{distribute, Distribution, Options};
+
+/// the sample input as json, to be changed before it is parsed
+pub fn get_input_value() -> Value {
+    serde_json::from_str(include_str!("../input.json")).unwrap()
+}
+
+/// the sample input with the given top level keys replaced
+pub fn get_input(changes: Value) -> JsonData {
+    let mut input = get_input_value();
+    if let (Some(input), Some(changes)) = (input.as_object_mut(), changes.as_object()) {
+        for (key, value) in changes.iter() {
+            input.insert(key.clone(), value.clone());
+        }
+    }
+    parse_value(&input).unwrap()
+}
+
+/// distributes without printing the progress
+pub fn distribute_quietly(input: &JsonData, options: Options) -> Distribution {
+    distribute(
+        input,
+        &Options {
+            quiet: true,
+            ..options
+        },
+    )
+}
@@ -0,0 +1,247 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cmp::Reverse;
+
+use types::*;
+use implementations::next_random;
+
+const DEFAULT_CURRICULUM_SIZE: u64 = 5;
+const DEFAULT_EXKURS_SIZE: u64 = 2;
+
+/// the groups in the order of the input
+#[derive(Debug, Clone)]
+pub struct Grouping {
+    pub curriculum: Vec<Vec<u64>>,
+    pub exkurs: Vec<Vec<u64>>,
+}
+
+/// The sizes of as many groups of at least the target size as possible. The groups differ by
+/// at most one student.
+fn get_sizes(count: usize, size: usize) -> Vec<usize> {
+    let group_count = (count / size).max(1);
+    (0..group_count)
+        .map(|index| count / group_count + if index < count % group_count { 1 } else { 0 })
+        .collect()
+}
+
+/// Merges the students that have to stay together into units, every other student is a unit
+/// of its own. The units keep the order of the students.
+fn get_units(students: &[u64], together: &[&Vec<u64>]) -> Vec<Vec<u64>> {
+    let mut units: Vec<Vec<u64>> = students.iter().map(|student| vec![*student]).collect();
+    for list in together.iter() {
+        let mut merged = Vec::new();
+        let mut position = None;
+        let mut index = 0;
+        while index < units.len() {
+            if units[index].iter().any(|student| list.contains(student)) {
+                position.get_or_insert(index);
+                merged.extend(units.remove(index));
+            } else {
+                index += 1;
+            }
+        }
+        if let Some(position) = position {
+            units.insert(position, merged);
+        }
+    }
+    units
+}
+
+/// whether the unit contains a student that has to be kept apart from one of the group
+fn is_apart(group: &[u64], unit: &[u64], apart: &[&Vec<u64>]) -> bool {
+    apart.iter().any(|list| {
+        group.iter().any(|student| list.contains(student))
+            && unit.iter().any(|student| list.contains(student))
+    })
+}
+
+/// the first unit that has to be together and apart at the same time
+fn find_contradiction<'a>(units: &'a [Vec<u64>], apart: &[&Vec<u64>]) -> Option<&'a Vec<u64>> {
+    units.iter().find(|unit| {
+        apart
+            .iter()
+            .any(|list| unit.iter().filter(|student| list.contains(student)).count() > 1)
+    })
+}
+
+/// Places the units into groups of the given sizes, the largest units first. Every unit goes
+/// into the group with the most free seats that holds no student it has to be kept apart from.
+/// Returns the groups and the units that did not fit.
+fn fill_groups(
+    mut units: Vec<Vec<u64>>,
+    sizes: &[usize],
+    apart: &[&Vec<u64>],
+) -> (Vec<Vec<u64>>, Vec<Vec<u64>>) {
+    units.sort_by_key(|unit| Reverse(unit.len()));
+    let mut groups: Vec<Vec<u64>> = vec![Vec::new(); sizes.len()];
+    let mut rest = Vec::new();
+    for unit in units {
+        let target = (0..groups.len())
+            .filter(|&index| sizes[index] - groups[index].len() >= unit.len())
+            .filter(|&index| !is_apart(&groups[index], &unit, apart))
+            .max_by_key(|&index| (sizes[index] - groups[index].len(), Reverse(index)));
+        match target {
+            Some(index) => groups[index].extend(unit),
+            None => rest.push(unit),
+        }
+    }
+    (groups, rest)
+}
+
+fn describe_rest(course_type: CourseType, unit: &[u64]) -> String {
+    format!(
+        "the students {:?} do not fit into any {:?} group, the groups are full or hold students they have to be kept apart from",
+        unit, course_type
+    )
+}
+
+/// The Exkurs groups inside of the Curriculum groups. Every Curriculum group is split on its
+/// own, so its groups differ by at most one student and no Exkurs group spans two of them.
+fn form_nested(
+    curriculum: &[Vec<u64>],
+    size: usize,
+    together: &[&Vec<u64>],
+    apart: &[&Vec<u64>],
+) -> Result<Vec<Vec<u64>>, String> {
+    let mut exkurs = Vec::new();
+    for group in curriculum.iter() {
+        let (groups, rest) = fill_groups(get_units(group, together), &get_sizes(group.len(), size), apart);
+        exkurs.extend(groups);
+        if rest.is_empty() {
+            continue;
+        }
+        // the students that are kept out of the groups by the rules form a smaller group
+        let count = rest.iter().map(Vec::len).sum();
+        let (groups, rest) = fill_groups(rest, &[count], apart);
+        if let Some(unit) = rest.first() {
+            return Err(describe_rest(CourseType::Exkurs, unit));
+        }
+        exkurs.extend(groups);
+    }
+    Ok(exkurs)
+}
+
+/// Forms the Curriculum and Exkurs groups of the students 1 to student_count with the sizes
+/// and constraints of the rules. Fails if a constraint can not be fulfilled.
+pub fn form_groups(student_count: u64, rules: &JsonGrouping) -> Result<Grouping, String> {
+    let curriculum_size = rules.curriculum.groesse.unwrap_or(DEFAULT_CURRICULUM_SIZE) as usize;
+    let exkurs_size = rules.exkurs.groesse.unwrap_or(DEFAULT_EXKURS_SIZE) as usize;
+    if curriculum_size == 0 || exkurs_size == 0 {
+        return Err("the size of the groups has to be at least 1".to_string());
+    }
+    for list in rules
+        .curriculum
+        .zusammen
+        .iter()
+        .chain(rules.curriculum.getrennt.iter())
+        .chain(rules.exkurs.zusammen.iter())
+        .chain(rules.exkurs.getrennt.iter())
+    {
+        if let Some(student) = list.iter().find(|student| **student < 1 || **student > student_count) {
+            return Err(format!("there is no student {}", student));
+        }
+    }
+    let mut students: Vec<u64> = (1..student_count + 1).collect();
+    if let Some(seed) = rules.startwert {
+        let mut state = seed;
+        for index in (1..students.len()).rev() {
+            let other = (next_random(&mut state) % (index as u64 + 1)) as usize;
+            students.swap(index, other);
+        }
+    }
+    let nested = rules.verschachtelt.unwrap_or(true);
+    let mut curriculum_together: Vec<&Vec<u64>> = rules.curriculum.zusammen.iter().collect();
+    if nested {
+        // students that share an Exkurs group have to share the Curriculum group as well
+        curriculum_together.extend(rules.exkurs.zusammen.iter());
+    }
+    let curriculum_apart: Vec<&Vec<u64>> = rules.curriculum.getrennt.iter().collect();
+    let exkurs_together: Vec<&Vec<u64>> = rules.exkurs.zusammen.iter().collect();
+    let exkurs_apart: Vec<&Vec<u64>> = rules.exkurs.getrennt.iter().collect();
+
+    let units = get_units(&students, &curriculum_together);
+    if let Some(unit) = find_contradiction(&units, &curriculum_apart) {
+        return Err(format!("the students {:?} have to be together and apart in Curriculum", unit));
+    }
+    let (mut curriculum, rest) = fill_groups(
+        units,
+        &get_sizes(students.len(), curriculum_size),
+        &curriculum_apart,
+    );
+    if let Some(unit) = rest.first() {
+        return Err(describe_rest(CourseType::Curriculum, unit));
+    }
+    let units = get_units(&students, &exkurs_together);
+    if let Some(unit) = find_contradiction(&units, &exkurs_apart) {
+        return Err(format!("the students {:?} have to be together and apart in Exkurs", unit));
+    }
+    let mut exkurs = if nested {
+        form_nested(&curriculum, exkurs_size, &exkurs_together, &exkurs_apart)?
+    } else {
+        let (groups, rest) = fill_groups(
+            units,
+            &get_sizes(students.len(), exkurs_size),
+            &exkurs_apart,
+        );
+        if let Some(unit) = rest.first() {
+            return Err(describe_rest(CourseType::Exkurs, unit));
+        }
+        groups
+    };
+    for group in curriculum.iter_mut().chain(exkurs.iter_mut()) {
+        group.sort();
+    }
+    exkurs.retain(|group| !group.is_empty());
+    Ok(Grouping { curriculum, exkurs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// whether every Exkurs group is inside of one Curriculum group
+    fn is_nested(grouping: &Grouping) -> bool {
+        grouping.exkurs.iter().all(|exkurs| {
+            grouping
+                .curriculum
+                .iter()
+                .any(|curriculum| exkurs.iter().all(|student| curriculum.contains(student)))
+        })
+    }
+
+    #[test]
+    fn forms_the_exkurs_groups_inside_of_the_curriculum_groups() {
+        let grouping = form_groups(26, &JsonGrouping::default()).unwrap();
+        assert!(is_nested(&grouping), "{:?}", grouping);
+        let mut students: Vec<u64> = grouping.exkurs.iter().flat_map(|group| group.iter().cloned()).collect();
+        students.sort();
+        assert_eq!(students, (1..27).collect::<Vec<u64>>());
+        assert!(grouping.exkurs.iter().all(|group| group.len() == 2 || group.len() == 3));
+    }
+
+    #[test]
+    fn forms_a_smaller_group_for_students_kept_apart() {
+        let mut rules = JsonGrouping::default();
+        rules.exkurs.getrennt = vec![vec![1, 2, 3]];
+        let grouping = form_groups(5, &rules).unwrap();
+        assert!(is_nested(&grouping), "{:?}", grouping);
+        assert_eq!(grouping.exkurs.len(), 3);
+        for group in grouping.exkurs.iter() {
+            assert_eq!(group.iter().filter(|student| **student <= 3).count(), 1, "{:?}", grouping);
+        }
+    }
+}
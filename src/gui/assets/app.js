@@ -0,0 +1,187 @@
+// The pages of alban serve. They only talk to the alban that serves them.
+"use strict";
+
+const DAY_NAMES = ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag"];
+const COURSE_TYPES = ["Curriculum", "Exkurs", "Zahnerhalt", "Zahnersatz"];
+
+let schedule = null; // the last distribution
+
+function $(id) {
+    return document.getElementById(id);
+}
+
+function showPage(name) {
+    document.querySelectorAll(".page").forEach(page => page.classList.toggle("active", page.id === name));
+    document.querySelectorAll("nav button").forEach(button => button.classList.toggle("active", button.dataset.page === name));
+    if (name === "schedule" && schedule === null) {
+        loadSchedule();
+    }
+}
+
+// shows the errors and problems of a JsonReport
+function showReport(list, report) {
+    list.innerHTML = "";
+    for (const [messages, kind] of [[report.fehler || [], "error"], [report.probleme || [], "problem"]]) {
+        for (const message of messages) {
+            const item = document.createElement("li");
+            item.className = kind;
+            item.textContent = message;
+            list.appendChild(item);
+        }
+    }
+}
+
+// answers of alban are json, errors as JsonReport
+async function request(method, url, body) {
+    const response = await fetch(url, { method, body });
+    const text = await response.text();
+    let json = null;
+    try {
+        json = JSON.parse(text);
+    } catch (error) {
+        json = { fehler: [text] };
+    }
+    return { ok: response.ok, json };
+}
+
+async function loadInput() {
+    const answer = await request("GET", "/api/input");
+    if (answer.ok) {
+        $("editor").value = JSON.stringify(answer.json, null, 4);
+        showReport($("input-report"), {});
+    } else {
+        showReport($("input-report"), answer.json);
+    }
+}
+
+async function saveInput(text, format) {
+    const answer = await request("PUT", "/api/input?format=" + format, text);
+    const report = answer.json;
+    if (answer.ok) {
+        report.probleme.unshift("saved");
+        await loadInput();
+    }
+    showReport($("input-report"), report);
+}
+
+function uploadInput(file) {
+    const extension = file.name.split(".").pop().toLowerCase();
+    const format = extension === "yml" ? "yaml" : extension;
+    const reader = new FileReader();
+    reader.onload = () => saveInput(reader.result, format);
+    reader.readAsText(file);
+}
+
+async function distribute() {
+    $("distribution-status").textContent = "distributing...";
+    showReport($("distribution-report"), {});
+    const answer = await request("POST", "/api/distribute");
+    if (answer.ok) {
+        setSchedule(answer.json);
+        $("distribution-status").textContent =
+            answer.json.wochen.length + " weeks were distributed and written to Alban says.txt and Alban says.json.";
+    } else {
+        $("distribution-status").textContent = "The distribution failed.";
+        showReport($("distribution-report"), answer.json);
+    }
+}
+
+async function loadSchedule() {
+    const answer = await request("GET", "/api/schedule");
+    if (answer.ok) {
+        setSchedule(answer.json);
+    } else {
+        $("grid").textContent = "There is no distribution yet.";
+    }
+}
+
+function setSchedule(json) {
+    schedule = json;
+    const select = $("week");
+    select.innerHTML = "";
+    const all = document.createElement("option");
+    all.value = "";
+    all.textContent = "all";
+    select.appendChild(all);
+    for (const week of schedule.wochen) {
+        const option = document.createElement("option");
+        option.value = week.nummer;
+        option.textContent = "KW " + week.nummer;
+        select.appendChild(option);
+    }
+    drawSchedule();
+}
+
+// the participants of a course, operators marked with *
+function drawParticipants(cell, course, student) {
+    for (const participant of course.teilnehmer) {
+        const span = document.createElement("span");
+        span.className = "student" + (participant === student ? " highlight" : "");
+        span.textContent = participant + ((course.behandler || []).includes(participant) ? "*" : "");
+        cell.appendChild(span);
+    }
+}
+
+// a table for every week, a row for every course and a column for every day
+function drawSchedule() {
+    const grid = $("grid");
+    grid.innerHTML = "";
+    if (schedule === null) {
+        return;
+    }
+    const selected = $("week").value;
+    const student = parseInt($("student").value, 10);
+    for (const week of schedule.wochen) {
+        if (selected !== "" && String(week.nummer) !== selected) {
+            continue;
+        }
+        const heading = document.createElement("h2");
+        heading.textContent = "KW " + week.nummer;
+        grid.appendChild(heading);
+
+        const rows = [];
+        for (const course of week.kurse) {
+            if (!rows.some(row => row.kurs === course.kurs && row.beginn === course.beginn)) {
+                rows.push({ kurs: course.kurs, beginn: course.beginn });
+            }
+        }
+        rows.sort((a, b) => COURSE_TYPES.indexOf(a.kurs) - COURSE_TYPES.indexOf(b.kurs) || a.beginn - b.beginn);
+
+        const table = document.createElement("table");
+        const header = table.insertRow();
+        header.appendChild(document.createElement("th"));
+        for (const day of DAY_NAMES) {
+            const cell = document.createElement("th");
+            cell.textContent = day;
+            header.appendChild(cell);
+        }
+        for (const row of rows) {
+            const line = table.insertRow();
+            const name = document.createElement("th");
+            name.textContent = row.kurs + " " + row.beginn;
+            line.appendChild(name);
+            for (let day = 1; day <= DAY_NAMES.length; day++) {
+                const cell = line.insertCell();
+                week.kurse
+                    .filter(course => course.tag === day && course.kurs === row.kurs && course.beginn === row.beginn)
+                    .forEach(course => drawParticipants(cell, course, student));
+            }
+        }
+        grid.appendChild(table);
+    }
+}
+
+document.querySelectorAll("nav button").forEach(button => button.addEventListener("click", () => showPage(button.dataset.page)));
+$("reload").addEventListener("click", loadInput);
+$("save").addEventListener("click", () => saveInput($("editor").value, "json"));
+$("upload").addEventListener("change", event => {
+    if (event.target.files.length > 0) {
+        uploadInput(event.target.files[0]);
+        event.target.value = "";
+    }
+});
+$("distribute").addEventListener("click", distribute);
+$("week").addEventListener("change", drawSchedule);
+$("student").addEventListener("input", drawSchedule);
+
+loadInput();
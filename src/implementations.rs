@@ -18,37 +18,203 @@ use types::*;
 use std::fmt;
 use std::rc::Rc;
 use std::ops::Deref;
+use std::collections::LinkedList;
 
-/// returns true is one of the group members already has an appointment at the same
-/// time of the given course
-impl<'a, 'b> Occupation<'a> for Group {
-    fn is_occupied(&self, course: &Course, day: &Day) -> bool {
+/// returns the course type if one of the group members already has an appointment at the
+/// same time of the given course
+impl<'a> Occupation<'a> for Group {
+    fn get_occupation(&self, course: &Course, day: &Day) -> Option<CourseType> {
         for student in self.participants.borrow().clone() {
-            if student.is_occupied(course, day) {
-                return true;
+            if let Some(course_type) = student.get_occupation(course, day) {
+                return Some(course_type);
             }
         }
-        false
+        None
     }
 }
 
-/// returns true this student already has an appointment at the same
-/// time of the given course
+/// returns the course type if this student already has an appointment at the same
+/// time of the given course or is pinned to another course at that time
 impl<'a> Occupation<'a> for Student {
-    fn is_occupied(&self, course: &Course, day: &Day) -> bool {
-        for ref course in day.courses
+    fn get_occupation(&self, course: &Course, day: &Day) -> Option<CourseType> {
+        for course in day.courses
             .borrow()
             .iter()
-            .filter(|ref x| x.beginning == course.beginning)
+            .filter(|x| x.beginning == course.beginning)
         //all courses with the same starting time
         {
             for student in course.participants.borrow().iter() {
                 if Rc::deref(student) == self {
-                    return true;
+                    return Some(course.course_type);
                 }
             }
+            for student in course.pinned.borrow().iter() {
+                if Rc::deref(student) == self {
+                    return Some(course.course_type);
+                }
+            }
+        }
+        None
+    }
+}
+
+impl Student {
+    /// returns true if the student is enrolled in the given week
+    pub fn is_enrolled(&self, week_number: u64) -> bool {
+        self.first_week <= week_number && week_number <= self.last_week
+    }
+}
+
+impl Group {
+    /// the participants that are enrolled in the given week
+    pub fn get_enrolled(&self, week_number: u64) -> Vec<Rc<Student>> {
+        self.participants
+            .borrow()
+            .iter()
+            .filter(|student| student.is_enrolled(week_number))
+            .cloned()
+            .collect()
+    }
+    /// returns true if the group lost more sessions to holidays than it made up
+    pub fn has_outstanding(&self) -> bool {
+        self.lost.get() > self.made_up.get()
+    }
+    /// returns true if the group visited as many sessions as its students require
+    pub fn has_reached_quota(&self) -> bool {
+        self.required.is_some_and(|required| self.sessions.get() >= required)
+    }
+    /// the numbers of all participants
+    pub fn get_numbers(&self) -> Vec<u64> {
+        self.participants
+            .borrow()
+            .iter()
+            .map(|student| student.number)
+            .collect()
+    }
+}
+
+impl JsonQuotas {
+    pub fn get(&self, course_type: CourseType) -> Option<u64> {
+        match course_type {
+            CourseType::Curriculum => self.Curriculum,
+            CourseType::Exkurs => self.Exkurs,
+            CourseType::Zahnerhalt => self.Zahnerhalt,
+            CourseType::Zahnersatz => self.Zahnersatz,
+        }
+    }
+    /// true if no course type has a value
+    pub fn is_empty(&self) -> bool {
+        COURSE_TYPES.iter().all(|course_type| self.get(*course_type).is_none())
+    }
+}
+
+/// the quotas in the order of COURSE_TYPES
+impl From<[Option<u64>; 4]> for JsonQuotas {
+    fn from(quotas: [Option<u64>; 4]) -> JsonQuotas {
+        JsonQuotas {
+            Curriculum: quotas[0],
+            Exkurs: quotas[1],
+            Zahnerhalt: quotas[2],
+            Zahnersatz: quotas[3],
+        }
+    }
+}
+
+/// the counts in the order of COURSE_TYPES
+impl From<[u64; 4]> for JsonCourseCounts {
+    fn from(counts: [u64; 4]) -> JsonCourseCounts {
+        JsonCourseCounts {
+            Curriculum: counts[0],
+            Exkurs: counts[1],
+            Zahnerhalt: counts[2],
+            Zahnersatz: counts[3],
+        }
+    }
+}
+
+impl JsonCourseCounts {
+    pub fn get(&self, course_type: CourseType) -> u64 {
+        match course_type {
+            CourseType::Curriculum => self.Curriculum,
+            CourseType::Exkurs => self.Exkurs,
+            CourseType::Zahnerhalt => self.Zahnerhalt,
+            CourseType::Zahnersatz => self.Zahnersatz,
+        }
+    }
+    /// the sum of all course types
+    pub fn total(&self) -> u64 {
+        self.Curriculum + self.Exkurs + self.Zahnerhalt + self.Zahnersatz
+    }
+}
+
+impl JsonHolidayRules {
+    pub fn get(&self, course_type: CourseType) -> JsonHolidayRule {
+        let rule = match course_type {
+            CourseType::Curriculum => self.Curriculum,
+            CourseType::Exkurs => self.Exkurs,
+            CourseType::Zahnerhalt => self.Zahnerhalt,
+            CourseType::Zahnersatz => self.Zahnersatz,
+        };
+        match (rule, course_type) {
+            (Some(rule), _) => rule,
+            (None, CourseType::Curriculum) => JsonHolidayRule::GanzeWoche,
+            (None, _) => JsonHolidayRule::NurFeiertag,
+        }
+    }
+}
+
+/// The rotations together with the names that identify them in the Json export.
+/// The Zahnersatz cohorts are numbered starting with 1.
+impl Rotations {
+    pub fn named(&self) -> Vec<(String, &LinkedList<Group>)> {
+        let mut named = vec![
+            ("Curriculum".to_string(), &self.curriculum),
+            ("Exkurs".to_string(), &self.exkurs),
+            ("Zahnerhalt".to_string(), &self.zahnerhalt),
+        ];
+        for (index, cohort) in self.zahnersatz.iter().enumerate() {
+            named.push((format!("Zahnersatz {}", index + 1), &cohort.groups));
+        }
+        named
+    }
+
+    pub fn named_mut(&mut self) -> Vec<(String, &mut LinkedList<Group>)> {
+        let mut named = vec![
+            ("Curriculum".to_string(), &mut self.curriculum),
+            ("Exkurs".to_string(), &mut self.exkurs),
+            ("Zahnerhalt".to_string(), &mut self.zahnerhalt),
+        ];
+        for (index, cohort) in self.zahnersatz.iter_mut().enumerate() {
+            named.push((format!("Zahnersatz {}", index + 1), &mut cohort.groups));
+        }
+        named
+    }
+}
+
+/// splitmix64, good enough to shuffle reproducibly without an extra dependency
+pub fn next_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+impl Rotations {
+    /// Shuffles the order of all rotations. The same seed always results in the same order.
+    pub fn shuffle(&mut self, seed: u64) {
+        let mut state = seed;
+        for (_, groups) in self.named_mut() {
+            let mut shuffled: Vec<Group> = Vec::with_capacity(groups.len());
+            while let Some(group) = groups.pop_front() {
+                shuffled.push(group);
+            }
+            for index in (1..shuffled.len()).rev() {
+                let other = (next_random(&mut state) % (index as u64 + 1)) as usize;
+                shuffled.swap(index, other);
+            }
+            groups.extend(shuffled);
         }
-        false
     }
 }
 
@@ -81,7 +247,7 @@ impl fmt::Display for Student {
 
 /// Prints exactly 15 students of the group, groups with less students will be filled
 /// with whitespace.
-impl<'a> fmt::Display for Group {
+impl fmt::Display for Group {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut result = Ok(());
         let participants = self.participants.borrow();
@@ -92,7 +258,7 @@ impl<'a> fmt::Display for Group {
             } else {
                 result = write!(f, "   ");
             }
-            if let Err(_) = result {
+            if result.is_err() {
                 break;
             }
         }
@@ -100,23 +266,103 @@ impl<'a> fmt::Display for Group {
     }
 }
 
-/// Prints exactly 15 list of the group, groups with less students will be filled
-/// with whitespace.
+/// Prints exactly as many students of the list as given by the width, shorter lists
+/// will be filled with whitespace.
 impl<'a> fmt::Display for StudentPrinter<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut result = Ok(());
         let participants = self.0;
         let mut participants_iterator = participants.iter();
-        for _ in 0..14 {
+        for _ in 0..self.1 {
             if let Some(student) = participants_iterator.next() {
                 result = write!(f, "{}", student);
             } else {
                 result = write!(f, "   ");
             }
-            if let Err(_) = result {
+            if result.is_err() {
                 break;
             }
         }
         result
     }
 }
+
+/// Prints the participants of a course like the StudentPrinter, but the operators of
+/// treatment pairs are followed by an asterisk instead of a comma.
+impl<'a> fmt::Display for CoursePrinter<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let participants = self.0.participants.borrow();
+        let operators = self.0.operators.borrow();
+        let mut participants_iterator = participants.iter();
+        for _ in 0..self.1 {
+            if let Some(student) = participants_iterator.next() {
+                let separator = if operators.contains(student) { '*' } else { ',' };
+                write!(f, "{:>2}{}", student.number, separator)?;
+            } else {
+                write!(f, "   ")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Prints a row of the statistics table, the sessions are followed by the quota if there is one.
+/// Sessions of earlier semesters are added with a plus.
+impl fmt::Display for StudentStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>7}", self.student.number)?;
+        for index in 0..self.sessions.len() {
+            let sessions = match self.credited[index] {
+                0 => format!("{}", self.sessions[index]),
+                credited => format!("{}+{}", self.sessions[index], credited),
+            };
+            let cell = match self.quotas[index] {
+                _ if self.exempt[index] => "befreit".to_string(),
+                Some(quota) => format!("{}/{}", sessions, quota),
+                None => sessions,
+            };
+            write!(f, " {:<10}", cell)?;
+        }
+        Ok(())
+    }
+}
+
+/// prints why a group was skipped
+impl fmt::Display for JsonSkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JsonSkipReason::Belegt(course_type) => write!(f, "gleichzeitig in {:?}", course_type),
+            JsonSkipReason::Verboten => write!(f, "durch eine Fixierung ausgeschlossen"),
+            JsonSkipReason::NichtEingeschrieben => write!(f, "nicht eingeschrieben"),
+            JsonSkipReason::KeinAusfall => write!(f, "keine ausgefallenen Sitzungen nachzuholen"),
+            JsonSkipReason::Vorrang => write!(f, "Gruppen mit Ausfällen hatten Vorrang"),
+            JsonSkipReason::KontingentErreicht => write!(f, "Kontingent erreicht, andere Gruppen hatten Vorrang"),
+        }
+    }
+}
+
+/// prints the week, day, course type and beginning of the course
+impl fmt::Display for JsonCourseSlot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let day = match DAY_NAMES.get((self.tag as usize).wrapping_sub(1)) {
+            Some(day) => day.to_string(),
+            None => format!("Tag {}", self.tag),
+        };
+        write!(f, "KW {} {:<10} {} {:>2}", self.woche, day, self.kurs, self.beginn)
+    }
+}
+
+/// Prints the week, day and course and if it was moved, the day it was moved to.
+impl fmt::Display for HolidayChange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "KW {} {:<10} {} {:>2}: ",
+            self.week, DAY_NAMES[self.day], self.course_type, self.beginning
+        )?;
+        match self.moved_to {
+            Some(day) => write!(f, "verschoben auf {}", DAY_NAMES[day]),
+            None => write!(f, "entfällt"),
+        }
+    }
+}
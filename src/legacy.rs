@@ -0,0 +1,215 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs::File;
+use std::io::Read;
+
+use types::*;
+
+/// the course type a row of the text output starts with
+fn get_row_type(line: &str) -> Option<CourseType> {
+    let name = line.split_whitespace().next()?;
+    COURSE_TYPES
+        .iter()
+        .find(|course_type| format!("{:?}", course_type) == name)
+        .cloned()
+}
+
+/// Reads the courses of a row. The participants are assigned to the day whose column
+/// contains the beginning of their number, operators are marked by an asterisk.
+/// Empty columns are skipped, they can not be told apart from courses that do not exist.
+fn parse_row(
+    line: &str,
+    line_number: usize,
+    column_width: usize,
+    course_type: CourseType,
+) -> Result<Vec<JsonScheduleCourse>, String> {
+    let mut columns: Vec<(Vec<u64>, Vec<u64>)> = vec![(Vec::new(), Vec::new()); 5];
+    // the prefix is the course type padded to 10 chars, the beginning and three spaces
+    let rest = line.get(10..).unwrap_or("").trim_start();
+    let beginning_length = rest.find(' ').unwrap_or(rest.len());
+    let beginning = match rest[..beginning_length].parse::<u8>() {
+        Ok(beginning) => beginning,
+        Err(_) => return Err(format!("line {}: the beginning of the course is missing", line_number)),
+    };
+    let start = line.len() - rest.len() + beginning_length + 3;
+    let content = line.get(start..).unwrap_or("");
+    let mut number = String::new();
+    let mut number_start = 0;
+    for (position, character) in content.char_indices().chain(Some((content.len(), ' '))) {
+        if character.is_ascii_digit() {
+            if number.is_empty() {
+                number_start = position;
+            }
+            number.push(character);
+            continue;
+        }
+        if number.is_empty() {
+            if character != ' ' && character != ',' && character != '*' {
+                return Err(format!("line {}: unexpected '{}'", line_number, character));
+            }
+            continue;
+        }
+        let student = match number.parse::<u64>() {
+            Ok(student) => student,
+            Err(error) => return Err(format!("line {}: {}", line_number, error)),
+        };
+        number.clear();
+        let day = (number_start / column_width).min(4);
+        columns[day].0.push(student);
+        if character == '*' {
+            columns[day].1.push(student);
+        }
+    }
+    Ok(columns
+        .into_iter()
+        .enumerate()
+        .filter(|&(_, (ref participants, _))| !participants.is_empty())
+        .map(|(day_index, (participants, operators))| JsonScheduleCourse {
+            tag: day_index as u64 + 1,
+            kurs: course_type,
+            beginn: beginning,
+            teilnehmer: participants,
+            behandler: operators,
+        })
+        .collect())
+}
+
+/// Parses the text output of a distribution ("Alban says.txt"), also the one of older
+/// versions and hand-edited ones as long as the students stay in the columns of their day.
+/// The rotation states are not part of the text, so they stay empty.
+pub fn parse_text(text: &str) -> Result<JsonSchedule, String> {
+    let mut weeks: Vec<JsonScheduleWeek> = Vec::new();
+    let mut column_width = None;
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        // the lines of the holidays also start with the week, but continue with the day
+        if let Some(number) = line
+            .strip_prefix("KW ")
+            .and_then(|number| number.trim().parse::<u64>().ok())
+        {
+            weeks.push(JsonScheduleWeek {
+                nummer: number,
+                rotationen: Vec::new(),
+                kurse: Vec::new(),
+            });
+            column_width = None;
+            continue;
+        }
+        let week = match weeks.last_mut() {
+            Some(week) => week,
+            None => continue,
+        };
+        if line.trim().is_empty() {
+            // a week ends with an empty line
+            column_width = None;
+        } else if line.trim_start().starts_with(DAY_NAMES[0]) {
+            // the day names are printed at the beginning of their columns
+            match (line.find(DAY_NAMES[0]), line.find(DAY_NAMES[1])) {
+                (Some(first), Some(second)) if second > first => column_width = Some(second - first),
+                _ => return Err(format!("line {}: the day names are incomplete", line_number)),
+            }
+        } else if let (Some(column_width), Some(course_type)) = (column_width, get_row_type(line)) {
+            week.kurse.extend(parse_row(line, line_number, column_width, course_type)?);
+        }
+    }
+    if weeks.is_empty() {
+        return Err("there is no week in the text".to_string());
+    }
+    Ok(JsonSchedule {
+        wochen: weeks,
+        rotationen: Vec::new(),
+    })
+}
+
+/// reads a distribution from its text output
+pub fn read_text_schedule(path: &str) -> JsonSchedule {
+    let mut text = String::new();
+    if let Err(error) = File::open(path).and_then(|mut file| file.read_to_string(&mut text)) {
+        panic!("Unable to read schedule {}: {}", path, error);
+    }
+    match parse_text(&text) {
+        Err(error) => panic!("Unable to parse schedule {}: {}", path, error),
+        Ok(schedule) => schedule,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::*;
+    use distribution::{generate_output, Options};
+    use fixtures::{distribute_quietly, get_input};
+    use statistics::{get_conflicts, get_statistics};
+
+    /// the courses with participants, in the same order for both schedules
+    fn get_courses(schedule: &JsonSchedule) -> Vec<(u64, Vec<JsonScheduleCourse>)> {
+        schedule
+            .wochen
+            .iter()
+            .map(|week| {
+                let mut courses: Vec<JsonScheduleCourse> = week
+                    .kurse
+                    .iter()
+                    .filter(|course| !course.teilnehmer.is_empty())
+                    .cloned()
+                    .collect();
+                courses.sort_by_key(|course| (course.tag, course.beginn, course.kurs as u8));
+                (week.nummer, courses)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reads_the_text_output_like_the_json_export() {
+        // the operators are only marked with the zahnersatz pairs
+        for changes in [serde_json::json!({}), serde_json::json!({"zahnersatzPaare": {"aktiv": true}})] {
+            let data = get_input(changes);
+            let distribution = distribute_quietly(&data, Options::default());
+            let statistics = get_statistics(
+                &data,
+                &distribution.weeks,
+                &distribution.students,
+                &distribution.rotations,
+            );
+            let mut text = Vec::new();
+            generate_output(
+                &mut text,
+                &distribution.weeks,
+                &distribution.holiday_changes,
+                &get_conflicts(&distribution.rotations),
+                &statistics,
+            )
+            .unwrap();
+            let parsed = parse_text(&String::from_utf8(text).unwrap()).unwrap();
+            assert_eq!(
+                serde_json::to_value(get_courses(&parsed)).unwrap(),
+                serde_json::to_value(get_courses(&distribution.schedule)).unwrap()
+            );
+            assert!(parsed.rotationen.is_empty());
+        }
+    }
+
+    #[test]
+    fn refuses_texts_without_weeks() {
+        assert_eq!(parse_text("Curriculum 7    1, 7,").unwrap_err(), "there is no week in the text");
+        assert_eq!(
+            parse_text("KW 14\nMontag\nCurriculum 7    1, 7,").unwrap_err(),
+            "line 2: the day names are incomplete"
+        );
+    }
+}
@@ -0,0 +1,43 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+#[macro_use]
+extern crate serde_derive;
+
+extern crate serde;
+extern crate serde_json;
+
+pub mod types;
+pub mod implementations;
+pub mod parser;
+pub mod distribution;
+pub mod pins;
+pub mod schedule;
+pub mod statistics;
+pub mod explain;
+pub mod diff;
+pub mod verify;
+pub mod edit;
+pub mod legacy;
+pub mod migrate;
+pub mod schema;
+pub mod roster;
+pub mod grouping;
+pub mod tui;
+pub mod server;
+pub mod attendance;
+#[cfg(test)]
+mod fixtures;
@@ -14,246 +14,592 @@
 // You should have received a copy of the GNU General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-#[macro_use]
-extern crate serde_derive;
-
-extern crate serde;
+extern crate alban;
 extern crate serde_json;
 
-mod types;
-mod implementations;
-mod parser;
-
-use std::collections::LinkedList;
 use std::fs::File;
-use std::io::Write;
-use std::io;
-use std::error::Error;
-use std::result::Result;
 
-use types::*;
-use parser::*;
+use alban::types::*;
+use alban::parser::*;
+use alban::schedule::*;
+use alban::statistics::*;
+use alban::explain::*;
+use alban::diff::*;
+use alban::verify::*;
+use alban::edit::*;
+use alban::migrate::*;
+use alban::schema::*;
+use alban::roster::*;
+use alban::grouping::*;
+use alban::distribution::*;
+use alban::tui::*;
+use alban::server::*;
+use alban::attendance::*;
 
-/// returns true if an entry exists for the given course type in the given day
-fn course_is_today(course_type: CourseType, day: &Day) -> bool {
-    day.courses
-        .borrow()
+/// returns the value following the given option in the command line arguments
+fn get_argument(arguments: &[String], option: &str) -> Option<String> {
+    arguments
         .iter()
-        .any(|course| course.course_type == course_type)
+        .position(|argument| argument == option)
+        .and_then(|position| arguments.get(position + 1))
+        .cloned()
 }
 
-/// subfunction of distribute_courses
-/// returns the left space (seats)
-fn distribute_course(
-    course: &Course,
-    day: &Day,
-    participants: &mut LinkedList<Group>,
-    space_count: u8,
-) -> u8 {
-    let mut space_count = space_count;
-    while space_count > 0 {
-        space_count -= 1;
-        let mut splitter = 0;
-        for ref group in participants.iter() {
-            if group.is_occupied(course, day) {
-                splitter = splitter + 1;
-            } else {
-                break;
-            }
+/// Returns the arguments after the command that are neither options nor their values.
+/// Options the command does not know are refused instead of being ignored.
+fn get_positional(arguments: &[String], options: &[&str]) -> Vec<String> {
+    let mut positional = Vec::new();
+    let mut index = 2;
+    while index < arguments.len() {
+        let argument = &arguments[index];
+        if options.contains(&argument.as_str()) {
+            index += 2;
+            continue;
         }
-        let mut rest = participants.split_off(splitter); // the due to occupation skipped part
-        let group = match rest.pop_front() {
-            // get relevant group
-            Some(group) => group,
-            None => continue,
+        if argument.starts_with("--") {
+            panic!("unknown option {}, alban {} accepts {}", argument, arguments[1], options.join(", "));
+        }
+        positional.push(argument.clone());
+        index += 1;
+    }
+    positional
+}
+
+/// The schedule and the input of verify and statistics. The input is given with --input or
+/// after the schedule.
+fn get_schedule_and_input(arguments: &[String]) -> (String, Option<String>) {
+    let usage = format!("alban {} <schedule> [--input input.json] [--format json|toml|yaml]", arguments[1]);
+    let positional = get_positional(arguments, &["--input", "--format"]);
+    let input = get_argument(arguments, "--input");
+    match (positional.as_slice(), input) {
+        ([schedule], input) => (schedule.clone(), input),
+        ([schedule, path], None) => (schedule.clone(), Some(path.clone())),
+        ([_, _], Some(_)) => panic!("the input is given twice: {}", usage),
+        _ => panic!("{} expects a schedule: {}", arguments[1], usage),
+    }
+}
+
+/// returns the day (1-5) of its number or name
+fn parse_day(day: &str) -> u64 {
+    match DAY_NAMES.iter().position(|name| name.eq_ignore_ascii_case(day)) {
+        Some(index) => index as u64 + 1,
+        None => match day.parse::<u64>() {
+            Ok(day) => day,
+            Err(error) => panic!("expected a day (1-5 or its name) instead of {}: {}", day, error),
+        },
+    }
+}
+
+/// returns the course type of its name
+fn parse_course_type(kurs: &str) -> CourseType {
+    match COURSE_TYPES.iter().find(|course_type| format!("{:?}", course_type) == kurs) {
+        Some(course_type) => *course_type,
+        None => panic!("expected one of {:?} instead of {}", COURSE_TYPES, kurs),
+    }
+}
+
+/// Parses the input file of the path or the one in the current working directory.
+/// The format is given by --format (json, toml or yaml) or by the extension of the file.
+fn parse_input(arguments: &[String], path: Option<&str>) -> JsonData {
+    let format = get_argument(arguments, "--format").map(|format| match format.as_str() {
+        "json" => InputFormat::Json,
+        "toml" => InputFormat::Toml,
+        "yaml" | "yml" => InputFormat::Yaml,
+        _ => panic!("--format expects json, toml or yaml instead of {}", format),
+    });
+    match (path, format) {
+        (Some(path), Some(format)) => parse_file_as(path, format),
+        (Some(path), None) => parse_file(path),
+        (None, Some(InputFormat::Json)) => parse_file("./input.json"),
+        (None, Some(InputFormat::Toml)) => parse_file("./input.toml"),
+        (None, Some(InputFormat::Yaml)) => parse_file("./input.yaml"),
+        (None, None) => parse(),
+    }
+}
+
+/// reads a course given as week/day/beginning, e.g. 14/2/7 or 14/Dienstag/7
+fn parse_slot(slot: &str, course_type: CourseType) -> JsonCourseSlot {
+    let parts: Vec<&str> = slot.split('/').collect();
+    if parts.len() != 3 {
+        panic!("expected a course as week/day/beginning instead of {}", slot);
+    }
+    JsonCourseSlot {
+        woche: match parts[0].parse() {
+            Ok(week) => week,
+            Err(error) => panic!("expected a week instead of {}: {}", parts[0], error),
+        },
+        tag: parse_day(parts[1]),
+        kurs: course_type,
+        beginn: match parts[2].parse() {
+            Ok(beginning) => beginning,
+            Err(error) => panic!("expected a beginning instead of {}: {}", parts[2], error),
+        },
+    }
+}
+
+/// Moves (alban move) or exchanges (alban swap) groups in a distribution. The changed
+/// distribution and its statistics are written like the distribution itself.
+fn run_edit(arguments: &[String]) {
+    let required = |option: &str| match get_argument(arguments, option) {
+        Some(value) => value,
+        None => panic!("{} is missing", option),
+    };
+    let path = get_argument(arguments, "--schedule").unwrap_or_else(|| "Alban says.json".to_string());
+    let mut schedule = read_schedule(&path);
+    let input = parse_input(arguments, get_argument(arguments, "--input").as_deref());
+    let course_type = parse_course_type(&required("--kurs"));
+    let placement = Placement {
+        student: match required("--student").parse() {
+            Ok(student) => student,
+            Err(error) => panic!("--student expects a student number: {}", error),
+        },
+        slot: parse_slot(&required("--from"), course_type),
+    };
+    let to = parse_slot(&required("--to"), course_type);
+    let options = EditOptions {
+        single: arguments.iter().any(|argument| argument == "--single"),
+        force: arguments.iter().any(|argument| argument == "--force"),
+    };
+    let result = if arguments[1] == "swap" {
+        let other = Placement {
+            student: match required("--with").parse() {
+                Ok(student) => student,
+                Err(error) => panic!("--with expects a student number: {}", error),
+            },
+            slot: to,
         };
-        rest.push_back(group); // move the relevant group to the back
-        participants.append(&mut rest); // reunite
-        if let Some(group) = participants.back() {
-            // get relevant group (old group was consumed by pushing)
-            let mut course_participants = course.participants.borrow_mut();
-            for student in group.participants.borrow_mut().iter_mut() {
-                course_participants.push_back(student.clone());
+        swap_students(&input, &mut schedule, &placement, &other, &options)
+    } else {
+        move_students(&input, &mut schedule, &placement, &to, &options)
+    };
+    match result {
+        Ok(warnings) => {
+            for warning in warnings.iter() {
+                println!("Warning: {}", warning);
             }
         }
+        Err(errors) => {
+            for error in errors.iter() {
+                println!("{}", error);
+            }
+            println!("the distribution was not changed, use --force to change it anyway");
+            std::process::exit(1);
+        }
+    }
+    // the text output can not be written back, so a text is changed into an export
+    let path = if path.ends_with(".txt") {
+        format!("{}.json", path.trim_end_matches(".txt"))
+    } else {
+        path
+    };
+    // the rotations continue from the changed courses
+    update_rotation_states(&input, &mut schedule);
+    write_schedule(&path, &schedule);
+    let distribution = restore_distribution(&input, schedule);
+    write_output(
+        &input,
+        &distribution.weeks,
+        &distribution.holiday_changes,
+        &distribution.students,
+        &distribution.rotations,
+    );
+}
+
+/// prints the statistics of a distribution that was read from a file (alban statistics)
+fn run_statistics(arguments: &[String]) {
+    let (schedule, input) = get_schedule_and_input(arguments);
+    let schedule = read_schedule(&schedule);
+    let input = parse_input(arguments, input.as_deref());
+    let distribution = restore_distribution(&input, schedule);
+    let statistics = get_statistics(
+        &input,
+        &distribution.weeks,
+        &distribution.students,
+        &distribution.rotations,
+    );
+    println!("---statistics---");
+    let _ = print_statistics(&mut std::io::stdout(), &statistics);
+}
+
+/// Reads the json export of a distribution. If the file is an input file instead, it is
+/// distributed first.
+fn load_schedule(path: &str) -> JsonSchedule {
+    if path.ends_with(".txt") {
+        return read_schedule(path);
+    }
+    let exported = File::open(path)
+        .ok()
+        .and_then(|file| serde_json::from_reader::<_, JsonSchedule>(file).ok());
+    match exported {
+        Some(schedule) => schedule,
+        None => distribute(&parse_file(path), &Options::default()).schedule,
     }
-    space_count
 }
 
-/// Takes a list of Groups and distributes them among the course.
-/// The distributed groups will be moved to the end of the given group list, so
-/// that the next time, they will be distributed with the least priority
-fn distribute_courses<'a, 'b>(
-    course_type: CourseType,
-    day: &'a Day,
-    participants: &'b mut LinkedList<Group>,
-) {
-    if course_is_today(course_type, day) {
-        match course_type {
-            CourseType::Curriculum => {
-                let courses = day.courses.borrow();
-                if let Some(course) = courses
-                    .iter()
-                    .find(|ref course| course.course_type == CourseType::Curriculum)
-                {
-                    distribute_course(&course, day, participants, 1);
-                }
+/// compares two distributions and writes the changes to "Alban diff.txt" and "Alban diff.json"
+fn run_diff(arguments: &[String]) {
+    let (old, new) = match (arguments.get(2), arguments.get(3)) {
+        (Some(old), Some(new)) => (load_schedule(old), load_schedule(new)),
+        _ => panic!("diff expects two schedules or input files: alban diff <old> <new>"),
+    };
+    let diff = diff_schedules(&old, &new);
+    println!("---diff---");
+    let _ = print_diff(&mut std::io::stdout(), &diff);
+    match File::create("Alban diff.txt") {
+        Err(error) => println!("couldn't create Alban diff.txt: {}", error),
+        Ok(mut file) => {
+            if let Err(error) = print_diff(&mut file, &diff) {
+                println!("Unable to write diff to file: {}", error);
             }
-            CourseType::Exkurs => {
-                let courses = day.courses.borrow();
-                if let Some(course) = courses
-                    .iter()
-                    .find(|ref course| course.course_type == CourseType::Exkurs)
-                {
-                    distribute_course(&course, day, participants, 1);
-                }
+        }
+    }
+    match File::create("Alban diff.json") {
+        Err(error) => println!("couldn't create Alban diff.json: {}", error),
+        Ok(file) => {
+            if let Err(error) = serde_json::to_writer_pretty(file, &diff) {
+                println!("Unable to write diff to file: {}", error);
             }
-            CourseType::Zahnersatz => {
-                let courses = day.courses.borrow();
-                if let Some(course) = courses.iter().find(|ref course| {
-                    course.course_type == CourseType::Zahnersatz && course.beginning == 7
-                }) {
-                    distribute_course(
-                        &course,
-                        day,
-                        participants,
-                        (get_zahnersatz_seat_count() / 2 as u8),
-                    );
-                }
-                if let Some(course) = courses.iter().find(|ref course| {
-                    course.course_type == CourseType::Zahnersatz && course.beginning == 16
-                }) {
-                    distribute_course(
-                        &course,
-                        day,
-                        participants,
-                        (get_zahnersatz_seat_count() / 2 as u8),
-                    );
-                }
+        }
+    }
+}
+
+/// Checks a distribution against the input ("input.json" if none is given) and reports all
+/// violations. Exits with 1 if there are any.
+fn run_verify(arguments: &[String]) {
+    let (schedule, input) = get_schedule_and_input(arguments);
+    let schedule = read_schedule(&schedule);
+    let input = parse_input(arguments, input.as_deref());
+    let violations = verify(&input, &schedule);
+    println!("---verify---");
+    for violation in violations.iter() {
+        println!("{}", violation);
+    }
+    println!("{} violations", violations.len());
+    if !violations.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// Compares the attendance with a distribution, writes the result to "Alban attendance.txt"
+/// and "Alban attendance.json" and an input that prioritizes the students with a deficit
+/// when the remaining weeks are distributed again (alban attendance).
+fn run_attendance(arguments: &[String]) {
+    let attendance = match arguments.get(2) {
+        Some(path) if !path.starts_with("--") => read_attendance(path),
+        _ => panic!(
+            "attendance expects an attendance file: alban attendance <attendance.json> \
+             [--schedule \"Alban says.json\"] [--input input.json] [--output \"Alban replan.json\"]"
+        ),
+    };
+    let schedule_path = get_argument(arguments, "--schedule").unwrap_or_else(|| "Alban says.json".to_string());
+    let input_path = get_argument(arguments, "--input").unwrap_or_else(|| get_input_path().to_string());
+    let output_path = get_argument(arguments, "--output").unwrap_or_else(|| "Alban replan.json".to_string());
+    let input = parse_input(arguments, Some(&input_path));
+    let report = reconcile(&input, &read_schedule(&schedule_path), &attendance);
+    println!("---attendance---");
+    let _ = print_attendance(&mut std::io::stdout(), &report);
+    match File::create("Alban attendance.txt") {
+        Err(error) => println!("couldn't create Alban attendance.txt: {}", error),
+        Ok(mut file) => {
+            if let Err(error) = print_attendance(&mut file, &report) {
+                println!("Unable to write attendance to file: {}", error);
             }
-            CourseType::Zahnerhalt => {
-                let courses = day.courses.borrow();
-                if let Some(course) = courses.iter().find(|ref course| {
-                    course.course_type == CourseType::Zahnerhalt && course.beginning == 7
-                }) {
-                    distribute_course(&course, day, participants, get_zahnerhalt_seat_count());
-                }
-                if let Some(course) = courses.iter().find(|ref course| {
-                    course.course_type == CourseType::Zahnerhalt && course.beginning == 16
-                }) {
-                    distribute_course(&course, day, participants, get_zahnerhalt_seat_count());
-                }
+        }
+    }
+    match File::create("Alban attendance.json") {
+        Err(error) => println!("couldn't create Alban attendance.json: {}", error),
+        Ok(file) => {
+            if let Err(error) = serde_json::to_writer_pretty(file, &report) {
+                println!("Unable to write attendance to file: {}", error);
             }
         }
     }
+    if report.bisWoche >= input.wochen.kwEnde {
+        println!("all weeks took place, there is nothing left to distribute again");
+        return;
+    }
+    let mut replan = read_input_value(&input_path);
+    let prioritized = add_deficits(&mut replan, &report);
+    write_input(&output_path, &replan);
+    println!(
+        "{} students have a deficit, their input for the remaining weeks was written to {}. Distribute them with",
+        prioritized.len(),
+        output_path
+    );
+    println!(
+        "    alban --input \"{}\" --from-week {} --previous \"{}\"",
+        output_path,
+        report.bisWoche + 1,
+        schedule_path
+    );
 }
 
-fn print_course<T: Write>(
-    file: &mut T,
-    week: &Week,
-    course_type: CourseType,
-    beginning: u8,
-) -> Result<(), io::Error> {
-    write!(file, "{} {}   ", course_type, beginning)?;
-    for day_index in 0..5 {
-        let current_day = &week.days[day_index];
+/// Writes the students and groups into an input. A gruppenImport is removed, the groups are
+/// now part of the input.
+fn set_groups(input: &mut serde_json::Value, count: u64, curriculum: &[Vec<u64>], exkurs: &[Vec<u64>]) {
+    if let Some(object) = input.as_object_mut() {
+        set_input_key(object, "studentenAnzahl", "studentCount", serde_json::Value::from(count));
+        set_input_key(object, "curriculumGruppen", "curriculumGroups", serde_json::Value::from(curriculum.to_vec()));
+        set_input_key(object, "exkursGruppen", "exkursGroups", serde_json::Value::from(exkurs.to_vec()));
+        object.remove("gruppenImport");
+        object.remove("rosterImport");
+    }
+}
 
-        if let Some(course) =
-            current_day.courses.borrow().iter().find(|ref course| {
-                course.course_type == course_type && course.beginning == beginning
-            }) {
-            write!(file, "{}", StudentPrinter(&course.participants.borrow()))?;
-        } else {
-            write!(file, "{}", StudentPrinter(&LinkedList::new()))?;
-        }
+/// Upgrades an input file to the current version. The original is kept as "<path>.bak".
+fn run_migrate(arguments: &[String]) {
+    let path = arguments.get(2).map_or("./input.json", String::as_str);
+    if InputFormat::from_path(path) != InputFormat::Json {
+        panic!("only json inputs can be migrated, the comments of toml and yaml inputs would be lost");
+    }
+    let value = read_input_value(path);
+    println!("---migrate---");
+    let (migrated, report) = match migrate(&value) {
+        Ok(migrated) => migrated,
+        Err(error) => panic!("Unable to migrate input {}: {}", path, error),
+    };
+    if report.is_empty() {
+        println!("{} already has version {}", path, INPUT_VERSION);
+        return;
+    }
+    for change in report.iter() {
+        println!("{}", change);
     }
-    writeln!(file, "")?;
-    Ok(())
+    back_up(path);
+    write_input(path, &migrated);
+    println!("{} migrated to version {}", path, INPUT_VERSION);
 }
 
-///takes in the data, formats it so that it is humanly readable and writes it to the given Writer
-fn generate_output<'a, T: Write>(file: &mut T, weeks: &'a Vec<Week>) -> Result<(), io::Error> {
-    for current_week in weeks {
-        writeln!(file, "KW {}", current_week.number)?;
-        writeln!(file, "               Montag                                    Dienstag                                  Mittwoch                                  Donnerstag                                Freitag")?;
-        print_course(file, &current_week, CourseType::Curriculum, 7)?;
-        print_course(file, &current_week, CourseType::Exkurs, 7)?;
-        print_course(file, &current_week, CourseType::Zahnerhalt, 7)?;
-        print_course(file, &current_week, CourseType::Zahnerhalt, 16)?;
-        print_course(file, &current_week, CourseType::Zahnersatz, 7)?;
-        print_course(file, &current_week, CourseType::Zahnersatz, 16)?;
-        writeln!(file, "")?;
-        writeln!(file, "")?;
-    }
-    Ok(())
+/// Writes the students and groups of a roster into an input file (alban import).
+fn run_import(arguments: &[String]) {
+    let roster_path = match arguments.get(2) {
+        Some(path) if !path.starts_with("--") => path.clone(),
+        _ => panic!("import expects a roster: alban import <roster.csv> [--input input.json] [--output input.json]"),
+    };
+    let input_path = get_argument(arguments, "--input").unwrap_or_else(|| "./input.json".to_string());
+    let output_path = get_argument(arguments, "--output").unwrap_or_else(|| input_path.clone());
+    if InputFormat::from_path(&input_path) != InputFormat::Json {
+        panic!("only json inputs can be imported into, the comments of toml and yaml inputs would be lost");
+    }
+    let mut input = read_input_value(&input_path);
+    let delimiter = get_argument(arguments, "--delimiter").map(|delimiter| match delimiter.as_str() {
+        "tab" | "\\t" => '\t',
+        _ if delimiter.chars().count() == 1 => delimiter.chars().next().unwrap_or(';'),
+        _ => panic!("--delimiter expects a single character instead of {}", delimiter),
+    });
+    let mapping = JsonRoster {
+        datei: roster_path.clone(),
+        trennzeichen: delimiter,
+        kennungSpalte: get_argument(arguments, "--id-column"),
+        nameSpalte: get_argument(arguments, "--name-column"),
+        nummerSpalte: get_argument(arguments, "--number-column"),
+        curriculumSpalte: get_argument(arguments, "--curriculum-column"),
+        exkursSpalte: get_argument(arguments, "--exkurs-column"),
+    };
+    let roster = read_roster(std::path::Path::new(&roster_path), &mapping);
+    println!("---import---");
+    println!("{:>3} {:<15} {:<30} {:<12} {:<12}", "Nr", "Kennung", "Name", "Curriculum", "Exkurs");
+    for student in roster.students.iter() {
+        println!(
+            "{:>3} {:<15} {:<30} {:<12} {:<12}",
+            student.number,
+            student.id,
+            student.name,
+            student.curriculum.as_ref().map_or("-", String::as_str),
+            student.exkurs.as_ref().map_or("-", String::as_str)
+        );
+    }
+    for warning in roster.warnings.iter() {
+        println!("Warning: {}", warning);
+    }
+    let count = roster.students.iter().map(|student| student.number).max().unwrap_or(0);
+    set_groups(&mut input, count, &roster.curriculum_groups, &roster.exkurs_groups);
+    back_up(&output_path);
+    write_input(&output_path, &input);
+    println!(
+        "{} students, {} Curriculum and {} Exkurs groups written to {}",
+        roster.students.len(),
+        roster.curriculum_groups.len(),
+        roster.exkurs_groups.len(),
+        output_path
+    );
+}
+
+/// Forms the Curriculum and Exkurs groups and writes them into the input (alban groups).
+fn run_groups(arguments: &[String]) {
+    let input_path = get_argument(arguments, "--input").unwrap_or_else(|| "./input.json".to_string());
+    let output_path = get_argument(arguments, "--output").unwrap_or_else(|| input_path.clone());
+    if InputFormat::from_path(&input_path) != InputFormat::Json {
+        panic!("only json inputs can be grouped, the comments of toml and yaml inputs would be lost");
+    }
+    let mut input = read_input_value(&input_path);
+    let parsed: JsonData = match serde_json::from_value(input.clone()) {
+        Ok(parsed) => parsed,
+        Err(error) => panic!("Unable to parse input {}: {}", input_path, error),
+    };
+    let size = |option: &str| {
+        get_argument(arguments, option).map(|size| match size.parse::<u64>() {
+            Ok(size) => size,
+            Err(error) => panic!("{} expects a number: {}", option, error),
+        })
+    };
+    let mut rules = parsed.gruppenBildung.clone().unwrap_or_default();
+    if let Some(size) = size("--curriculum-size") {
+        rules.curriculum.groesse = Some(size);
+    }
+    if let Some(size) = size("--exkurs-size") {
+        rules.exkurs.groesse = Some(size);
+    }
+    if let Some(seed) = size("--seed") {
+        rules.startwert = Some(seed);
+    }
+    let grouping = match form_groups(parsed.studentenAnzahl, &rules) {
+        Ok(grouping) => grouping,
+        Err(error) => panic!("Unable to form the groups: {}", error),
+    };
+    println!("---groups---");
+    for (index, group) in grouping.curriculum.iter().enumerate() {
+        let exkurs: Vec<&Vec<u64>> = grouping
+            .exkurs
+            .iter()
+            .filter(|exkurs| exkurs.iter().any(|student| group.contains(student)))
+            .collect();
+        println!("Curriculum {:>2}: {:?}, Exkurs {:?}", index + 1, group, exkurs);
+    }
+    set_groups(&mut input, parsed.studentenAnzahl, &grouping.curriculum, &grouping.exkurs);
+    back_up(&output_path);
+    write_input(&output_path, &input);
+    println!(
+        "{} Curriculum and {} Exkurs groups written to {}",
+        grouping.curriculum.len(),
+        grouping.exkurs.len(),
+        output_path
+    );
+}
+
+/// serves the web interface on localhost (alban serve)
+fn run_serve(arguments: &[String]) {
+    let port = get_argument(arguments, "--port").map_or(8080, |port| match port.parse::<u16>() {
+        Ok(port) => port,
+        Err(error) => panic!("--port expects a port number: {}", error),
+    });
+    let path = get_argument(arguments, "--input").unwrap_or_else(|| get_input_path().to_string());
+    let assets = get_argument(arguments, "--assets").map(std::path::PathBuf::from);
+    // only this computer can reach the interface
+    serve(&format!("127.0.0.1:{}", port), &path, assets.as_deref());
 }
 
 /// The MAIN function... very important
 fn main() {
     println!("---start---");
-    let input = parse();
+    let arguments: Vec<String> = std::env::args().collect();
+    if arguments.get(1).map(String::as_str) == Some("diff") {
+        run_diff(&arguments);
+        return;
+    }
+    if arguments.get(1).map(String::as_str) == Some("schema") {
+        // editors find it with "$schema": "./input.schema.json" in the input
+        let path = arguments.get(2).map_or("input.schema.json", String::as_str);
+        write_schema(path);
+        println!("the schema of the input was written to {}", path);
+        return;
+    }
+    if arguments.get(1).map(String::as_str) == Some("tui") {
+        run_tui(arguments.get(2).map_or(get_input_path(), String::as_str));
+        return;
+    }
+    if arguments.get(1).map(String::as_str) == Some("serve") {
+        run_serve(&arguments);
+        return;
+    }
+    if arguments.get(1).map(String::as_str) == Some("groups") {
+        run_groups(&arguments);
+        return;
+    }
+    if arguments.get(1).map(String::as_str) == Some("import") {
+        run_import(&arguments);
+        return;
+    }
+    if arguments.get(1).map(String::as_str) == Some("migrate") {
+        run_migrate(&arguments);
+        return;
+    }
+    if arguments.get(1).map(String::as_str) == Some("verify") {
+        run_verify(&arguments);
+        return;
+    }
+    if arguments.get(1).map(String::as_str) == Some("attendance") {
+        run_attendance(&arguments);
+        return;
+    }
+    if arguments.get(1).map(String::as_str) == Some("statistics") {
+        run_statistics(&arguments);
+        return;
+    }
+    if arguments.get(1).map(String::as_str) == Some("move")
+        || arguments.get(1).map(String::as_str) == Some("swap")
+    {
+        run_edit(&arguments);
+        return;
+    }
+    // weeks before this one are taken from a previous distribution
+    let from_week = get_argument(&arguments, "--from-week").map(|week| match week.parse::<u64>() {
+        Ok(week) => week,
+        Err(error) => panic!("--from-week expects a week number: {}", error),
+    });
+    let previous = get_argument(&arguments, "--previous").map(|path| read_schedule(&path));
+    // the rotations start where the previous semester ended
+    let previous_semester =
+        get_argument(&arguments, "--previous-semester").map(|path| read_schedule(&path));
+    // answers why the student got or did not get the seats of the filtered courses
+    let why = get_argument(&arguments, "--why").map(|student| match student.parse::<u64>() {
+        Ok(student) => student,
+        Err(error) => panic!("--why expects a student number: {}", error),
+    });
+    let filter = ExplainFilter {
+        week: get_argument(&arguments, "--week").map(|week| match week.parse::<u64>() {
+            Ok(week) => week,
+            Err(error) => panic!("--week expects a week number: {}", error),
+        }),
+        day: get_argument(&arguments, "--day").map(|day| parse_day(&day)),
+        course_type: get_argument(&arguments, "--kurs").map(|kurs| parse_course_type(&kurs)),
+    };
+    // every seat decision is written to "Alban explains.json"
+    let explain = arguments.iter().any(|argument| argument == "--explain") || why.is_some();
+    if from_week.is_some() != previous.is_some() {
+        panic!("--from-week and --previous have to be given together");
+    }
+    let options = Options {
+        from_week,
+        previous,
+        previous_semester,
+        explain,
+        quiet: false,
+    };
+    let input = parse_input(&arguments, get_argument(&arguments, "--input").as_deref());
     println!("---parsed json file---");
     println!("{}", serde_json::to_string_pretty(&input).unwrap());
-    let weeks = get_weeks(&input);
-    println!("---parsed weeks---",);
-    //println!("{:#?}", weeks);
-    let students = get_students(&input);
-    println!("---parsed students---",);
-    for student in students.iter() {
-        println!("{}", student)
-    }
-    let mut curriculum_groups = get_curriculum_groups(&input, &students);
-    println!("---parsed curriculum groups---",);
-    for group in curriculum_groups.iter() {
-        println!("{}", group)
-    }
-    let mut exkurs_groups = get_exkurs_groups(&input, &students);
-    println!("---parsed exkurs groups---",);
-    for group in exkurs_groups.iter() {
-        println!("{}", group)
-    }
-    let mut zahnersatz_groups = get_zahnersatz_groups(&students);
-    println!("---parsed Zahnersatz groups---",);
-    println!("first half:");
-    for group in zahnersatz_groups.0.iter() {
-        println!("{}", group)
-    }
-    println!("second half:");
-    for group in zahnersatz_groups.1.iter() {
-        println!("{}", group)
-    }
-    let mut zahnerhalt_groups = get_zahnerhalt_groups(&students);
-    println!("---parsed Zahnerhalt groups---",);
-    for current_week in weeks.iter() {
-        println!("---process week {}---", current_week.number);
-        for day_index in 0..5 {
-            println!("---process day {}---", day_index);
-            let current_day = &current_week.days[day_index];
-            distribute_courses(CourseType::Curriculum, current_day, &mut curriculum_groups);
-            distribute_courses(CourseType::Exkurs, current_day, &mut exkurs_groups);
-            distribute_courses(
-                CourseType::Zahnersatz,
-                current_day,
-                &mut zahnersatz_groups.0,
-            );
-            distribute_courses(
-                CourseType::Zahnersatz,
-                current_day,
-                &mut zahnersatz_groups.1,
-            );
-            distribute_courses(CourseType::Zahnerhalt, current_day, &mut zahnerhalt_groups);
-        }
+    let Distribution {
+        weeks,
+        holiday_changes,
+        students,
+        rotations,
+        schedule,
+        decisions,
+    } = distribute(&input, &options);
+    write_output(&input, &weeks, &holiday_changes, &students, &rotations);
+    write_schedule("Alban says.json", &schedule);
+    if options.explain {
+        write_decisions("Alban explains.json", &decisions);
     }
-    let mut file = match File::create("Alban says.txt") {
-        Err(why) => panic!(
-            "couldn't create {}: {}",
-            "Alban says.txt",
-            why.description()
-        ),
-        Ok(file) => file,
-    };
-    let _ = generate_output(&mut std::io::stdout(), &weeks);
-    if let Err(err) = generate_output(&mut file, &weeks) {
-        println!("Unable to write output to file: {}", err.description());
+    if let Some(student) = why {
+        println!("---explain student {}---", student);
+        let explanations = explain_student(&decisions, student, &filter);
+        if explanations.is_empty() {
+            println!("student {} was not considered for any of these courses", student);
+        }
+        for explanation in explanations {
+            println!("{}", explanation);
+        }
     }
 }
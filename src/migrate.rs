@@ -0,0 +1,210 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+extern crate serde;
+extern crate serde_json;
+extern crate serde_yaml;
+extern crate toml;
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use self::serde::Serialize;
+use self::serde_json::{Map, Value};
+
+use types::*;
+use schema::get_schema;
+use parser::InputFormat;
+
+/// an empty input of the current version that only has to be filled
+pub const INPUT_TEMPLATE: &str = r#"{
+    "version": 2,
+    "studentenAnzahl": 0,
+    "wochen": { "kwAnfang": 1, "kwEnde": 2 },
+    "feiertage": [],
+    "exkursGruppen": [],
+    "curriculumGruppen": []
+}"#;
+
+/// Returns the version of an input file. Files without a version are from the first
+/// release (version 1).
+pub fn get_version(value: &Value) -> u64 {
+    value.get("version").and_then(Value::as_u64).unwrap_or(1)
+}
+
+/// the version is written at the beginning of the file, nothing else changed
+fn migrate_1_to_2(value: &mut Value, report: &mut Vec<String>) {
+    if let Some(object) = value.as_object_mut() {
+        let entries: Vec<(String, Value)> = std::mem::take(object).into_iter().collect();
+        object.insert("version".to_string(), Value::from(2));
+        for (key, value) in entries.into_iter().filter(|(key, _)| key != "version") {
+            object.insert(key, value);
+        }
+        report.push("added version 2".to_string());
+    }
+}
+
+/// Records the paths of the keys of the value that the schema does not know.
+fn find_unknown(value: &Value, schema: &Value, path: &str, unknown: &mut Vec<String>) {
+    match value {
+        Value::Object(object) => {
+            let properties = match schema.get("properties").and_then(Value::as_object) {
+                Some(properties) => properties,
+                None => return,
+            };
+            for (key, child) in object.iter() {
+                match properties.get(key) {
+                    Some(child_schema) => find_unknown(child, child_schema, &format!("{}{}.", path, key), unknown),
+                    None => unknown.push(format!("{}{}", path, key)),
+                }
+            }
+        }
+        Value::Array(array) => {
+            let items = match schema.get("items") {
+                Some(items) => items,
+                None => return,
+            };
+            let path = path.trim_end_matches('.');
+            for (index, child) in array.iter().enumerate() {
+                find_unknown(child, items, &format!("{}[{}].", path, index), unknown);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns the paths of all keys of the input that are not part of the input format,
+/// neither in German nor in English. The input types reject these keys on their own, the
+/// paths only explain the rejection.
+pub fn get_unknown_keys(value: &Value) -> Vec<String> {
+    let mut unknown = Vec::new();
+    find_unknown(value, &get_schema(), "", &mut unknown);
+    unknown
+}
+
+/// Upgrades an input file of an older version to the current one. Unknown keys are most
+/// likely misspelled, the upgrade is refused until they are corrected.
+/// Returns the upgraded input together with a description of every change.
+pub fn migrate(value: &Value) -> Result<(Value, Vec<String>), String> {
+    let mut migrated = value.clone();
+    let mut report = Vec::new();
+    let version = get_version(value);
+    if version > INPUT_VERSION {
+        return Err(format!(
+            "the input has version {}, but only version {} is known",
+            version, INPUT_VERSION
+        ));
+    }
+    let unknown = get_unknown_keys(value);
+    if !unknown.is_empty() {
+        return Err(format!(
+            "the input has the unknown keys {}, correct or remove them first",
+            unknown.join(", ")
+        ));
+    }
+    if version < 2 {
+        migrate_1_to_2(&mut migrated, &mut report);
+    }
+    if let Err(error) = serde_json::from_value::<JsonData>(migrated.clone()) {
+        return Err(format!("the upgraded input is still invalid: {}", error));
+    }
+    Ok((migrated, report))
+}
+
+/// writes an input file with the indentation of four spaces the examples use
+pub fn write_input(path: &str, value: &Value) {
+    let mut output = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"    ");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut output, formatter);
+    if let Err(error) = value.serialize(&mut serializer) {
+        panic!("Unable to write input {}: {}", path, error);
+    }
+    output.push(b'\n');
+    if let Err(error) = File::create(path).and_then(|mut file| file.write_all(&output)) {
+        panic!("Unable to write input {}: {}", path, error);
+    }
+}
+
+/// Sets a key of an input object. An existing key keeps its language, new keys are written
+/// in German.
+pub fn set_input_key(object: &mut Map<String, Value>, german: &str, english: &str, value: Value) {
+    if object.contains_key(english) {
+        object.insert(english.to_string(), value);
+    } else {
+        object.insert(german.to_string(), value);
+    }
+}
+
+/// parses the text of an input in the given format without checking it against the input format
+pub fn parse_input_value(text: &str, format: InputFormat) -> Result<Value, String> {
+    match format {
+        InputFormat::Json => serde_json::from_str(text).map_err(|error| error.to_string()),
+        InputFormat::Toml => toml::from_str(text).map_err(|error| error.to_string()),
+        InputFormat::Yaml => serde_yaml::from_str(text).map_err(|error| error.to_string()),
+    }
+}
+
+/// reads an input in the format of its extension without checking it against the input format
+pub fn read_input_value(path: &str) -> Value {
+    let mut text = String::new();
+    if let Err(error) = File::open(path).and_then(|mut file| file.read_to_string(&mut text)) {
+        panic!("Unable to read input {}: {}", path, error);
+    }
+    match parse_input_value(&text, InputFormat::from_path(path)) {
+        Ok(value) => value,
+        Err(error) => panic!("Unable to parse input {}: {}", path, error),
+    }
+}
+
+/// keeps a copy of a file that is about to be overwritten as "<path>.bak"
+pub fn back_up(path: &str) {
+    if !Path::new(path).exists() {
+        return;
+    }
+    let backup = format!("{}.bak", path);
+    if let Err(error) = std::fs::copy(path, &backup) {
+        panic!("Unable to back up {} to {}: {}", path, backup, error);
+    }
+    println!("the previous {} is kept in {}", path, backup);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixtures::get_input_value;
+
+    fn get_input() -> Value {
+        let mut input = get_input_value();
+        input["feiertagsregeln"] = serde_json::json!({"Zahnerhalt": "verschieben"});
+        input["wochen"]["kwEnde "] = Value::from(25);
+        input
+    }
+
+    #[test]
+    fn refuses_to_migrate_misspelled_keys() {
+        let error = migrate(&get_input()).unwrap_err();
+        assert!(error.contains("wochen.kwEnde , feiertagsregeln,"), "{}", error);
+    }
+
+    #[test]
+    fn the_input_types_reject_unknown_keys() {
+        let mut input = get_input();
+        input.as_object_mut().unwrap().remove("feiertagsregeln");
+        let error = serde_json::from_value::<JsonData>(input).unwrap_err();
+        assert!(error.to_string().starts_with("unknown field `kwEnde `"), "{}", error);
+    }
+}
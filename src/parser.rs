@@ -15,62 +15,464 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 extern crate serde_json;
+extern crate serde_yaml;
+extern crate toml;
 
 use std::collections::LinkedList;
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::fs::File;
-use std::error::Error;
+use std::io::Read;
+use std::path::Path;
 use serde_json::error::Category;
+use serde_json::Value;
 
 use types::*;
+use migrate::get_unknown_keys;
+use roster::*;
+use statistics::{get_required, is_exempt};
 
 macro_rules! push_course {
     (
         $beginning:expr,
         $course_type:expr,
         $courses:expr
+    ) => (
+        push_course!($beginning, $course_type, $courses, CourseKind::Regular);
+    );
+    (
+        $beginning:expr,
+        $course_type:expr,
+        $courses:expr,
+        $kind:expr
     ) => (
         $courses.push_back(Course {
         beginning: $beginning,
         course_type: $course_type,
+        kind: $kind,
         participants: RefCell::new(LinkedList::new()),
+        operators: RefCell::new(LinkedList::new()),
+        pinned: RefCell::new(LinkedList::new()),
+        forbidden: RefCell::new(LinkedList::new()),
         });
     );
 }
 
-/// Parses "input.json" in the current working directory and reports possible errors on std out
-pub fn parse() -> JsonData {
-    match File::open("./input.json") {
-        Err(error) => {
-            panic!("Unable to open json file: {}", error.description());
+/// the file formats the input can be written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    Json,
+    Toml, // allows comments
+    Yaml, // allows comments
+}
+
+impl InputFormat {
+    /// the format of a file by its extension, json for all unknown extensions
+    pub fn from_path(path: &str) -> InputFormat {
+        let path = path.to_lowercase();
+        if path.ends_with(".toml") {
+            InputFormat::Toml
+        } else if path.ends_with(".yaml") || path.ends_with(".yml") {
+            InputFormat::Yaml
+        } else {
+            InputFormat::Json
         }
-        Ok(file) => match serde_json::from_reader(file) {
+    }
+}
+
+/// The input in the current working directory, the first of "input.json", "input.toml",
+/// "input.yaml" and "input.yml" that exists.
+pub fn get_input_path() -> &'static str {
+    ["./input.json", "./input.toml", "./input.yaml", "./input.yml"]
+        .iter()
+        .find(|path| Path::new(path).exists())
+        .unwrap_or(&"./input.json")
+}
+
+/// Parses the input in the current working directory and reports possible errors on std out.
+pub fn parse() -> JsonData {
+    parse_file(get_input_path())
+}
+
+/// Parses the given input file in the format of its extension and reports possible errors
+/// on std out.
+pub fn parse_file(path: &str) -> JsonData {
+    parse_file_as(path, InputFormat::from_path(path))
+}
+
+/// Parses the given input file in the given format and reports possible errors on std out.
+/// Keys that are not part of the input format are rejected and listed.
+pub fn parse_file_as(path: &str, format: InputFormat) -> JsonData {
+    let mut text = String::new();
+    if let Err(error) = File::open(path).and_then(|mut file| file.read_to_string(&mut text)) {
+        panic!("Unable to open input file: {}", error);
+    }
+    let mut data = match format {
+        InputFormat::Json => parse_json(&text),
+        InputFormat::Toml => match toml::from_str::<JsonData>(&text) {
             Err(error) => {
-                println!("{}", error.description());
-                println!("line: {}, column {}", error.line(), error.column());
-                match error.classify() {
-                    Category::Io => {println!("IOError: unable to read stream")}
-                    Category::Syntax => {println!("SyntaxError: file has malformed JSON. Did you miss or add brackets, colons etc?")}
-                    Category::Data => {println!("DataError: parsed type does not match the expected type. Did you miss or add \"'s or confused arrays and objects?\nIf this error occurs on the last line, you might have misspelled a key?")}
-                    Category::Eof => {println!("EOFError: premature end of file")}
-                }
-                panic!("Failed to parse json file");
+                println!("{}", error);
+                print_unknown_keys(toml::from_str::<Value>(&text).ok());
+                panic!("Failed to parse toml file");
+            }
+            Ok(data) => data,
+        },
+        InputFormat::Yaml => match serde_yaml::from_str::<JsonData>(&text) {
+            Err(error) => {
+                println!("{}", error);
+                print_unknown_keys(serde_yaml::from_str::<Value>(&text).ok());
+                panic!("Failed to parse yaml file");
             }
             Ok(data) => data,
         },
+    };
+    import_groups(&mut data, path);
+    match data.version {
+        None => println!("Warning: the input has no version, it can be upgraded with alban migrate"),
+        Some(version) if version > INPUT_VERSION => panic!(
+            "the input has version {}, but only version {} is known",
+            version, INPUT_VERSION
+        ),
+        Some(version) if version < INPUT_VERSION => {
+            println!("Warning: the input has version {}, it can be upgraded with alban migrate", version)
+        }
+        Some(_) => {}
+    }
+    data
+}
+
+/// Problems of the input that would make the distribution fail or produce nonsense.
+pub fn get_problems(data: &JsonData) -> Vec<String> {
+    let mut problems = Vec::new();
+    if data.wochen.kwEnde < data.wochen.kwAnfang {
+        problems.push(format!(
+            "the last week {} is before the first week {}",
+            data.wochen.kwEnde, data.wochen.kwAnfang
+        ));
+    }
+    if data.studentenAnzahl == 0 {
+        problems.push("there are no students".to_string());
+    }
+    for (course_type, groups) in [(CourseType::Curriculum, &data.curriculumGruppen), (CourseType::Exkurs, &data.exkursGruppen)].iter() {
+        let name = format!("{:?}", course_type);
+        if groups.is_empty() {
+            problems.push(format!("there are no {} groups", name));
+        }
+        let mut seen = Vec::new();
+        for (index, group) in groups.iter().enumerate() {
+            for student in group.iter() {
+                if *student < 1 || *student > data.studentenAnzahl {
+                    problems.push(format!("{} group {}: there is no student {}", name, index + 1, student));
+                } else if seen.contains(student) {
+                    problems.push(format!("{} group {}: student {} is in two groups", name, index + 1, student));
+                }
+                seen.push(*student);
+            }
+        }
+        for student in 1..data.studentenAnzahl + 1 {
+            // exempt students leave their groups anyway
+            if !seen.contains(&student) && !is_exempt(data, student, *course_type) {
+                problems.push(format!("student {} is in no {} group", student, name));
+            }
+        }
+    }
+    problems.extend(get_immovable_courses(data));
+    for student in get_paired_twice(&data.zahnersatzPaare.paare) {
+        problems.push(format!("student {} is in more than one Zahnersatz pair", student));
+    }
+    let split = &data.zahnersatzAufteilung;
+    if split.art == JsonSplitMethod::Liste || split.art == JsonSplitMethod::CurriculumGruppen {
+        if split.haelften.is_empty() {
+            problems.push("the cohorts of the Zahnersatz split are missing".to_string());
+        }
+        for student in 1..data.studentenAnzahl + 1 {
+            let cohorts = get_listed_cohorts(data, student);
+            if cohorts.len() > 1 {
+                problems.push(format!("student {} is in more than one Zahnersatz cohort", student));
+            } else if cohorts.is_empty() && !split.haelften.is_empty() && !is_exempt(data, student, CourseType::Zahnersatz) {
+                problems.push(format!("student {} is in no Zahnersatz cohort", student));
+            }
+        }
+    }
+    for holiday in data.feiertage.iter() {
+        if holiday.woche < data.wochen.kwAnfang || holiday.woche > data.wochen.kwEnde {
+            problems.push(format!("the holiday in KW {} is outside of the semester", holiday.woche));
+        }
+    }
+    problems
+}
+
+/// Replaces the groups with the ones of the gruppenImport, the roster is next to the input
+/// file of the path. Inputs without an import need groups of their own.
+pub fn import_groups(data: &mut JsonData, path: &str) {
+    match data.gruppenImport.clone() {
+        Some(mapping) => {
+            // the roster is next to the input
+            let roster_path = match Path::new(path).parent() {
+                Some(directory) => directory.join(&mapping.datei),
+                None => Path::new(&mapping.datei).to_path_buf(),
+            };
+            let roster = read_roster(&roster_path, &mapping);
+            for warning in roster.warnings.iter() {
+                println!("Warning: {}", warning);
+            }
+            if let Err(error) = apply_roster(data, &roster) {
+                panic!("Unable to import the groups from {}: {}", mapping.datei, error);
+            }
+        }
+        None => {
+            if data.curriculumGruppen.is_empty() || data.exkursGruppen.is_empty() {
+                panic!("the input needs curriculumGruppen and exkursGruppen or a gruppenImport");
+            }
+        }
+    }
+}
+
+/// Reads an input that was already parsed, e.g. one that was sent to the web interface.
+/// Returns all errors instead of stopping at the first. A gruppenImport is not applied.
+pub fn parse_value(value: &Value) -> Result<JsonData, Vec<String>> {
+    let data: JsonData = match serde_json::from_value(value.clone()) {
+        Ok(data) => data,
+        Err(error) => {
+            // serde only names the first unknown key, the paths of all of them are clearer
+            let unknown = get_unknown_keys(value);
+            if unknown.is_empty() {
+                return Err(vec![error.to_string()]);
+            }
+            return Err(unknown.iter().map(|key| format!("unknown key: {}", key)).collect());
+        }
+    };
+    match data.version {
+        Some(version) if version > INPUT_VERSION => Err(vec![format!(
+            "the input has version {}, but only version {} is known",
+            version, INPUT_VERSION
+        )]),
+        _ => Ok(data),
+    }
+}
+
+/// Lists the keys of an input that failed to parse that are not part of the input format.
+fn print_unknown_keys(value: Option<Value>) {
+    let unknown = value.map(|value| get_unknown_keys(&value)).unwrap_or_default();
+    for key in unknown.iter() {
+        println!("unknown key: {}", key);
+    }
+    if !unknown.is_empty() {
+        println!("Did you misspell these keys? Inputs of older versions can be upgraded with alban migrate.");
+    }
+}
+
+/// parses json input and explains the error categories
+fn parse_json(text: &str) -> JsonData {
+    match serde_json::from_str(text) {
+        Err(error) => {
+            println!("{}", error);
+            println!("line: {}, column {}", error.line(), error.column());
+            match error.classify() {
+                Category::Io => {println!("IOError: unable to read stream")}
+                Category::Syntax => {println!("SyntaxError: file has malformed JSON. Did you miss or add brackets, colons etc?")}
+                Category::Data => {
+                    print_unknown_keys(serde_json::from_str(text).ok());
+                    println!("DataError: parsed type does not match the expected type. Did you miss or add \"'s or confused arrays and objects?\nIf this error occurs on the last line, you might have misspelled a key?\nInputs of older versions can be upgraded with alban migrate, alban schema describes all keys.")
+                }
+                Category::Eof => {println!("EOFError: premature end of file")}
+            }
+            panic!("Failed to parse json file");
+        }
+        Ok(data) => data,
+    }
+}
+
+/// the courses of a regular day as course type and beginning
+fn get_regular_courses(day_index: usize) -> Vec<(CourseType, u8)> {
+    let mut courses = vec![(CourseType::Curriculum, 7), (CourseType::Exkurs, 7)];
+    if day_index != 4 {
+        courses.push((CourseType::Zahnerhalt, 7));
+    }
+    if day_index == 2 {
+        courses.push((CourseType::Zahnerhalt, 16));
+    }
+    if day_index != 2 {
+        courses.push((CourseType::Zahnersatz, 7));
+    }
+    if day_index == 4 {
+        courses.push((CourseType::Zahnersatz, 16));
+    }
+    courses
+}
+
+/// Course types with a course on every day can not be moved to another day because of a
+/// holiday. Returns a message for every such course type that should be moved.
+fn get_immovable_courses(parsed_data: &JsonData) -> Vec<String> {
+    COURSE_TYPES
+        .iter()
+        .filter(|course_type| parsed_data.feiertagsRegeln.get(**course_type) == JsonHolidayRule::Verschieben)
+        .filter(|course_type| {
+            (0..5).all(|day_index| get_regular_courses(day_index).iter().any(|&(other, _)| other == **course_type))
+        })
+        .map(|course_type| {
+            format!(
+                "feiertagsRegeln: {:?} runs every day and can not be moved, use ganzeWoche or nurFeiertag",
+                course_type
+            )
+        })
+        .collect()
+}
+
+/// The order the course types are distributed in during the given week. Course types that
+/// come first win if students would be needed in two courses at the same time.
+/// Without "reihenfolge" the order is Curriculum, Exkurs, Zahnersatz, Zahnerhalt.
+pub fn get_course_order(parsed_data: &JsonData, week_number: u64) -> Vec<CourseType> {
+    let order = match parsed_data.reihenfolge {
+        Some(ref order) => order,
+        None => {
+            return vec![
+                CourseType::Curriculum,
+                CourseType::Exkurs,
+                CourseType::Zahnersatz,
+                CourseType::Zahnerhalt,
+            ]
+        }
+    };
+    for course_type in COURSE_TYPES.iter() {
+        if order.kurse.iter().filter(|kurs| *kurs == course_type).count() != 1 {
+            panic!("{:?} has to be listed exactly once in the reihenfolge", course_type);
+        }
+    }
+    let mut course_order = order.kurse.clone();
+    if order.rotieren {
+        let shift = week_number.saturating_sub(parsed_data.wochen.kwAnfang) as usize;
+        let length = course_order.len();
+        course_order.rotate_left(shift % length);
+    }
+    course_order
+}
+
+/// Returns true if a holiday covers the course. Holidays may be limited to some course
+/// types and to the courses that begin in a time range.
+fn is_holiday(
+    parsed_data: &JsonData,
+    week_number: u64,
+    day_index: usize,
+    course_type: CourseType,
+    beginning: u8,
+) -> bool {
+    parsed_data.feiertage.iter().any(|feiertag_entry| {
+        feiertag_entry.woche == week_number
+            && feiertag_entry.tage.iter().any(|&day| day == day_index as u64 + 1)
+            && (feiertag_entry.kurse.is_empty() || feiertag_entry.kurse.contains(&course_type))
+            && feiertag_entry.ab.is_none_or(|from| from <= beginning)
+            && feiertag_entry.bis.is_none_or(|until| beginning < until)
+    })
+}
+
+/// Applies the "feiertagsRegeln" to all weeks and returns every regular course that does
+/// not take place or takes place on another day because of a holiday.
+/// By default a Curriculum course is dropped for the whole week if a holiday covers one of
+/// its courses, all other courses are only dropped if they are covered themselves.
+/// Moved courses go to the next day of the week without such a course (or the closest day
+/// before, if there is none after).
+pub fn get_holiday_changes(parsed_data: &JsonData) -> Vec<HolidayChange> {
+    if let Some(message) = get_immovable_courses(parsed_data).first() {
+        panic!("{}", message);
+    }
+    let mut changes = Vec::new();
+    for week_number in parsed_data.wochen.kwAnfang..parsed_data.wochen.kwEnde + 1 {
+        let covered = |day_index: usize, course_type: CourseType, beginning: u8| {
+            is_holiday(parsed_data, week_number, day_index, course_type, beginning)
+        };
+        for day_index in 0..5 {
+            for (course_type, beginning) in get_regular_courses(day_index) {
+                let rule = parsed_data.feiertagsRegeln.get(course_type);
+                if !covered(day_index, course_type, beginning) {
+                    let week_has_holiday = (0..5).any(|other_day| {
+                        get_regular_courses(other_day)
+                            .iter()
+                            .any(|&(other_type, other_beginning)| {
+                                other_type == course_type
+                                    && covered(other_day, other_type, other_beginning)
+                            })
+                    });
+                    if rule == JsonHolidayRule::GanzeWoche && week_has_holiday {
+                        changes.push(HolidayChange {
+                            week: week_number,
+                            day: day_index,
+                            course_type,
+                            beginning,
+                            moved_to: None,
+                        });
+                    }
+                    continue;
+                }
+                let mut moved_to = None;
+                if rule == JsonHolidayRule::Verschieben {
+                    let candidates = (day_index + 1..5).chain((0..day_index).rev());
+                    moved_to = candidates.into_iter().find(|&target| {
+                        !covered(target, course_type, beginning)
+                            && !get_regular_courses(target).contains(&(course_type, beginning))
+                            && !changes.iter().any(|change: &HolidayChange| {
+                                change.week == week_number
+                                    && change.moved_to == Some(target)
+                                    && change.course_type == course_type
+                                    && change.beginning == beginning
+                            })
+                    });
+                }
+                changes.push(HolidayChange {
+                    week: week_number,
+                    day: day_index,
+                    course_type,
+                    beginning,
+                    moved_to,
+                });
+            }
+        }
+    }
+    changes
+}
+
+/// the make-up courses of the given day, make-up courses on holidays are reported
+fn get_makeup_slots(parsed_data: &JsonData, week_number: u64, day_index: usize) -> Vec<JsonCourseSlot> {
+    let slots: Vec<JsonCourseSlot> = match parsed_data.nachholen {
+        Some(ref makeup) => makeup
+            .termine
+            .iter()
+            .filter(|slot| slot.woche == week_number && slot.tag == day_index as u64 + 1)
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    };
+    for slot in slots.iter() {
+        if is_holiday(parsed_data, week_number, day_index, slot.kurs, slot.beginn) {
+            panic!("make-up course {:?} is on a holiday", slot);
+        }
+    }
+    slots
+}
+
+/// Returns the cancelled courses of the given day. They are used to find out which groups
+/// would have visited them.
+pub fn get_cancelled_courses(changes: &[HolidayChange], week_number: u64, day_index: usize) -> Day {
+    let mut courses = LinkedList::new();
+    for change in changes
+        .iter()
+        .filter(|change| change.week == week_number && change.day == day_index && change.moved_to.is_none())
+    {
+        push_course!(change.beginning, change.course_type, courses, CourseKind::Cancelled);
+    }
+    Day {
+        courses: RefCell::new(courses),
     }
 }
 
 /// Generates all weeks and its children.
-/// Every day gets all courses unless it is a parsed holiday. Then it gets no course.
-/// Additionally in a week with at least one holiday, there will be no Curriculum course.
+/// Every day gets all its regular courses unless they are dropped or moved because of a
+/// holiday (see get_holiday_changes). Moved courses are added to the day they are moved to.
 /// all courses will have an empty list of participants assigned to them.
-pub fn get_weeks(parsed_data: &JsonData) -> Vec<Week> {
+pub fn get_weeks(parsed_data: &JsonData, changes: &[HolidayChange]) -> Vec<Week> {
     let mut weeks = Vec::with_capacity(20);
     let start = parsed_data.wochen.kwAnfang;
     let end = parsed_data.wochen.kwEnde + 1;
-    let parsed_feiertags = &parsed_data.feiertage;
     if start > end {
         panic!("first week is after the last week");
     }
@@ -79,27 +481,34 @@ pub fn get_weeks(parsed_data: &JsonData) -> Vec<Week> {
             number: week_index,
             days: {
                 let mut ret: [Day; 5] = Default::default();
-                for day_index in 0..5 {
-                    ret[day_index] = Day {
-                        courses: {
-                            RefCell::new({
-                                let mut courses = LinkedList::new();
-                                if let Some(entry) = parsed_feiertags.iter().find(
-                                    |&feiertag_entry| feiertag_entry.woche == week_index as u64,
-                                ) {
-                                    if let None =
-                                        entry.tage.iter().find(|&&day| day - 1 == day_index as u64)
-                                    {
-                                        push_courses(&mut courses, day_index);
-                                    }
-                                } else {
-                                    push_course!(7, CourseType::Curriculum, courses);
-                                    push_courses(&mut courses, day_index);
-                                }
-                                courses
-                            })
-                        },
+                for (day_index, day) in ret.iter_mut().enumerate() {
+                    let mut courses = LinkedList::new();
+                    for (course_type, beginning) in get_regular_courses(day_index) {
+                        if !changes.iter().any(|change| {
+                            change.week == week_index
+                                && change.day == day_index
+                                && change.course_type == course_type
+                                && change.beginning == beginning
+                        }) {
+                            push_course!(beginning, course_type, courses);
+                        }
+                    }
+                    for change in changes.iter().filter(|change| {
+                        change.week == week_index && change.moved_to == Some(day_index)
+                    }) {
+                        push_course!(change.beginning, change.course_type, courses);
                     }
+                    for slot in get_makeup_slots(parsed_data, week_index, day_index) {
+                        if courses.iter().any(|course| {
+                            course.course_type == slot.kurs && course.beginning == slot.beginn
+                        }) {
+                            panic!("make-up course {:?} collides with a regular course", slot);
+                        }
+                        push_course!(slot.beginn, slot.kurs, courses, CourseKind::MakeUp);
+                    }
+                    *day = Day {
+                        courses: RefCell::new(courses),
+                    };
                 }
                 ret
             },
@@ -109,36 +518,41 @@ pub fn get_weeks(parsed_data: &JsonData) -> Vec<Week> {
     weeks
 }
 
-fn push_courses(courses: &mut LinkedList<Course>, day_index: usize) {
-    push_course!(7, CourseType::Exkurs, courses);
-    if day_index != 4 {
-        push_course!(7, CourseType::Zahnerhalt, courses);
-    }
-    if day_index == 2 {
-        push_course!(16, CourseType::Zahnerhalt, courses);
-    }
-    if day_index != 2 {
-        push_course!(7, CourseType::Zahnersatz, courses);
-    }
-    if day_index == 4 {
-        push_course!(16, CourseType::Zahnersatz, courses);
-    }
-}
-
 /// Generates the list of students (a consecutive list of numbers, because there is
-/// nothing more of importance to a student)
-pub fn get_students<'a>(parsed_data: &JsonData) -> LinkedList<Rc<Student>> {
+/// nothing more of importance to a student). Students are enrolled for the whole semester
+/// unless "studenten" says otherwise, students that are never enrolled are left out.
+pub fn get_students(parsed_data: &JsonData) -> LinkedList<Rc<Student>> {
     let student_count = parsed_data.studentenAnzahl + 1;
+    let first_week = parsed_data.wochen.kwAnfang;
+    let last_week = parsed_data.wochen.kwEnde;
+    for enrollment in parsed_data.studenten.iter() {
+        if enrollment.nummer < 1 || enrollment.nummer >= student_count {
+            println!("Warning: there is no student {}", enrollment.nummer);
+        }
+    }
     let mut students = LinkedList::new();
     for i in 1..student_count {
-        let student = Rc::new(Student { number: i });
+        let enrollment = parsed_data.studenten.iter().find(|student| student.nummer == i);
+        let student = Rc::new(Student {
+            number: i,
+            first_week: enrollment
+                .and_then(|enrollment| enrollment.von)
+                .unwrap_or(first_week),
+            last_week: enrollment
+                .and_then(|enrollment| enrollment.bis)
+                .unwrap_or(last_week),
+        });
+        if student.first_week > last_week || student.last_week < first_week {
+            println!("Warning: student {} is never enrolled", i);
+            continue;
+        }
         students.push_back(student);
     }
     students
 }
 
 /// parse and generate
-pub fn get_curriculum_groups<'a>(
+pub fn get_curriculum_groups(
     parsed_data: &JsonData,
     students: &LinkedList<Rc<Student>>,
 ) -> LinkedList<Group> {
@@ -159,6 +573,12 @@ pub fn get_curriculum_groups<'a>(
                 }
                 RefCell::new(participants)
             },
+            sessions: Cell::new(0),
+            rotating_roles: false,
+            lost: Cell::new(0),
+            made_up: Cell::new(0),
+            conflicts: Cell::new(0),
+            required: None,
         };
         groups.push_back(group);
     }
@@ -166,7 +586,7 @@ pub fn get_curriculum_groups<'a>(
 }
 
 /// parse and generate
-pub fn get_exkurs_groups<'a>(
+pub fn get_exkurs_groups(
     parsed_data: &JsonData,
     students: &LinkedList<Rc<Student>>,
 ) -> LinkedList<Group> {
@@ -187,55 +607,267 @@ pub fn get_exkurs_groups<'a>(
                 }
                 RefCell::new(participants)
             },
+            sessions: Cell::new(0),
+            rotating_roles: false,
+            lost: Cell::new(0),
+            made_up: Cell::new(0),
+            conflicts: Cell::new(0),
+            required: None,
         };
         groups.push_back(group);
     }
     groups
 }
 
-/// parse and generate
-pub fn get_zahnersatz_groups<'a>(
+/// The cohorts that list the student, by its number or by its curriculum group. Only the
+/// "liste" and "curriculumGruppen" splits list their students.
+fn get_listed_cohorts(parsed_data: &JsonData, student: u64) -> Vec<usize> {
+    let split = &parsed_data.zahnersatzAufteilung;
+    split
+        .haelften
+        .iter()
+        .enumerate()
+        .filter(|&(_, cohort)| match split.art {
+            JsonSplitMethod::Liste => cohort.studenten.contains(&student),
+            JsonSplitMethod::CurriculumGruppen => cohort.gruppen.iter().any(|&group_number| {
+                parsed_data
+                    .curriculumGruppen
+                    .get((group_number as usize).wrapping_sub(1))
+                    .is_some_and(|group| group.contains(&student))
+            }),
+            JsonSplitMethod::Position | JsonSplitMethod::GeradeUngerade => false,
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Splits the students into the Zahnersatz cohorts like configured in "zahnersatzAufteilung".
+/// Without configuration the student list is split in two halves by position.
+/// Every student of a cohort forms its own group.
+pub fn get_zahnersatz_cohorts(
+    parsed_data: &JsonData,
     students: &LinkedList<Rc<Student>>,
-) -> (LinkedList<Group>, LinkedList<Group>) {
-    let mut first_half = LinkedList::new();
-    let mut second_half = LinkedList::new();
-    let mut position = 0;
+) -> Vec<Cohort> {
+    let split = &parsed_data.zahnersatzAufteilung;
+    let cohort_count = match split.art {
+        JsonSplitMethod::Position => split.haelften.len().max(2),
+        JsonSplitMethod::GeradeUngerade => {
+            if split.haelften.len() > 2 {
+                panic!("an odd/even split can only have two cohorts");
+            }
+            2
+        }
+        JsonSplitMethod::Liste | JsonSplitMethod::CurriculumGruppen => {
+            if split.haelften.is_empty() {
+                panic!("the cohorts of the Zahnersatz split are missing");
+            }
+            split.haelften.len()
+        }
+    };
+    let mut members: Vec<LinkedList<Rc<Student>>> = vec![LinkedList::new(); cohort_count];
+    let chunk_size = (students.len() / cohort_count).max(1);
+    for (position, student) in students.iter().enumerate() {
+        let cohort_index = match split.art {
+            JsonSplitMethod::Position => Some((position / chunk_size).min(cohort_count - 1)),
+            JsonSplitMethod::GeradeUngerade => Some(((student.number + 1) % 2) as usize),
+            JsonSplitMethod::Liste | JsonSplitMethod::CurriculumGruppen => {
+                let cohorts = get_listed_cohorts(parsed_data, student.number);
+                if cohorts.len() > 1 {
+                    panic!("student {} is in more than one Zahnersatz cohort", student.number);
+                }
+                cohorts.first().cloned()
+            }
+        };
+        match cohort_index {
+            Some(index) => members[index].push_back(student.clone()),
+            None => println!(
+                "Warning: student {} is in no Zahnersatz cohort",
+                student.number
+            ),
+        }
+    }
+    members
+        .into_iter()
+        .enumerate()
+        .map(|(index, participants)| Cohort {
+            groups: if parsed_data.zahnersatzPaare.aktiv {
+                get_zahnersatz_pairs(parsed_data, &participants)
+            } else {
+                get_zahnersatz_groups(&participants)
+            },
+            slots: match split.haelften.get(index) {
+                Some(cohort) => cohort.termine.clone(),
+                None => Vec::new(),
+            },
+        })
+        .collect()
+}
+
+/// the students that are listed more than once in the Zahnersatz pairs
+fn get_paired_twice(pairs: &[Vec<u64>]) -> Vec<u64> {
+    let mut seen = Vec::new();
+    let mut twice = Vec::new();
+    for student in pairs.iter().flat_map(|pair| pair.iter()) {
+        if seen.contains(student) && !twice.contains(student) {
+            twice.push(*student);
+        }
+        seen.push(*student);
+    }
+    twice
+}
+
+/// Forms the treatment pairs of a cohort. Listed pairs are taken as they are, the
+/// remaining students are paired in order of the cohort. A student left over works alone.
+fn get_zahnersatz_pairs(
+    parsed_data: &JsonData,
+    students: &LinkedList<Rc<Student>>,
+) -> LinkedList<Group> {
+    if let Some(student) = get_paired_twice(&parsed_data.zahnersatzPaare.paare).first() {
+        panic!("student {} is in more than one Zahnersatz pair", student);
+    }
+    let mut pairs = Vec::new();
+    let mut unpaired = Vec::new();
+    for student in students.iter() {
+        match parsed_data
+            .zahnersatzPaare
+            .paare
+            .iter()
+            .find(|pair| pair.contains(&student.number))
+        {
+            Some(pair) => {
+                if pair.len() != 2 {
+                    panic!("Zahnersatz pair {:?} does not have two students", pair);
+                }
+                let partner_number = if pair[0] == student.number { pair[1] } else { pair[0] };
+                let partner = match students.iter().find(|partner| partner.number == partner_number) {
+                    Some(partner) => partner.clone(),
+                    None => panic!("Zahnersatz pair {:?} is not part of a single cohort", pair),
+                };
+                if pair[0] == student.number {
+                    pairs.push(vec![student.clone(), partner]);
+                }
+            }
+            None => unpaired.push(student.clone()),
+        }
+    }
+    for chunk in unpaired.chunks(2) {
+        pairs.push(chunk.to_vec());
+    }
+    let mut groups = LinkedList::new();
+    for pair in pairs {
+        let group = Group {
+            group_type: CourseType::Zahnersatz,
+            participants: RefCell::new(pair.into_iter().collect()),
+            sessions: Cell::new(0),
+            rotating_roles: true,
+            lost: Cell::new(0),
+            made_up: Cell::new(0),
+            conflicts: Cell::new(0),
+            required: None,
+        };
+        groups.push_back(group);
+    }
+    groups
+}
+
+/// parse and generate
+fn get_zahnersatz_groups(students: &LinkedList<Rc<Student>>) -> LinkedList<Group> {
+    let mut groups = LinkedList::new();
     for student in students.iter() {
         let group = Group {
-            group_type: CourseType::Zahnerhalt,
+            group_type: CourseType::Zahnersatz,
             participants: {
                 let mut participants = LinkedList::new();
                 participants.push_back(student.clone());
                 RefCell::new(participants)
             },
+            sessions: Cell::new(0),
+            rotating_roles: false,
+            lost: Cell::new(0),
+            made_up: Cell::new(0),
+            conflicts: Cell::new(0),
+            required: None,
         };
-        if position < students.len() / (2 as usize) {
-            first_half.push_back(group);
-        } else {
-            second_half.push_back(group)
+        groups.push_back(group);
+    }
+    groups
+}
+
+/// all groups of the input in their initial order
+pub fn get_rotations(parsed_data: &JsonData, students: &LinkedList<Rc<Student>>) -> Rotations {
+    Rotations {
+        curriculum: apply_overrides(parsed_data, get_curriculum_groups(parsed_data, students)),
+        exkurs: apply_overrides(parsed_data, get_exkurs_groups(parsed_data, students)),
+        zahnerhalt: apply_overrides(parsed_data, get_zahnerhalt_groups(students)),
+        zahnersatz: get_zahnersatz_cohorts(parsed_data, students)
+            .into_iter()
+            .map(|cohort| Cohort {
+                groups: apply_overrides(parsed_data, cohort.groups),
+                slots: cohort.slots,
+            })
+            .collect(),
+    }
+}
+
+/// Removes the students that are exempt from the course type from their groups and drops
+/// the groups that are left empty. Every group gets the sessions it needs until all its
+/// students reach their quotas.
+fn apply_overrides(parsed_data: &JsonData, groups: LinkedList<Group>) -> LinkedList<Group> {
+    let mut remaining = LinkedList::new();
+    for mut group in groups {
+        let course_type = group.group_type;
+        let participants: LinkedList<Rc<Student>> = group
+            .participants
+            .borrow()
+            .iter()
+            .filter(|student| !is_exempt(parsed_data, student.number, course_type))
+            .cloned()
+            .collect();
+        if participants.is_empty() {
+            continue;
         }
-        position += 1;
+        group.required = participants
+            .iter()
+            .filter_map(|student| get_required(parsed_data, student, course_type))
+            .max();
+        group.participants = RefCell::new(participants);
+        remaining.push_back(group);
     }
-    (first_half, second_half)
+    remaining
 }
 
 /// parse and generate
-pub fn get_zahnerhalt_groups<'a>(students: &LinkedList<Rc<Student>>) -> LinkedList<Group> {
+pub fn get_zahnerhalt_groups(students: &LinkedList<Rc<Student>>) -> LinkedList<Group> {
     let mut groups = LinkedList::new();
     for student in students.iter() {
         let group = Group {
-            group_type: CourseType::Zahnersatz,
+            group_type: CourseType::Zahnerhalt,
             participants: {
                 let mut participants = LinkedList::new();
                 participants.push_back(student.clone());
                 RefCell::new(participants)
             },
+            sessions: Cell::new(0),
+            rotating_roles: false,
+            lost: Cell::new(0),
+            made_up: Cell::new(0),
+            conflicts: Cell::new(0),
+            required: None,
         };
         groups.push_back(group);
     }
     groups
 }
 
+/// the seats a course offers to a single rotation
+pub fn get_seat_count(course_type: CourseType) -> u8 {
+    match course_type {
+        CourseType::Curriculum | CourseType::Exkurs => 1,
+        CourseType::Zahnerhalt => get_zahnerhalt_seat_count(),
+        CourseType::Zahnersatz => get_zahnersatz_seat_count(),
+    }
+}
+
 /// given by the client (available work stations)
 pub fn get_zahnerhalt_seat_count() -> u8 {
     11
@@ -245,3 +877,122 @@ pub fn get_zahnerhalt_seat_count() -> u8 {
 pub fn get_zahnersatz_seat_count() -> u8 {
     10
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixtures::get_input;
+
+    fn get_pairs(input: &JsonData) -> Vec<Vec<u64>> {
+        get_zahnersatz_pairs(input, &get_students(input))
+            .iter()
+            .map(Group::get_numbers)
+            .collect()
+    }
+
+    /// the changes of week 14 with a holiday on monday
+    fn get_monday_changes(rules: Value) -> Vec<(CourseType, u8, usize, Option<usize>)> {
+        let input = get_input(serde_json::json!({
+            "feiertage": [{"woche": 14, "tage": [1]}],
+            "feiertagsRegeln": rules
+        }));
+        get_holiday_changes(&input)
+            .iter()
+            .filter(|change| change.week == 14)
+            .map(|change| (change.course_type, change.beginning, change.day, change.moved_to))
+            .collect()
+    }
+
+    #[test]
+    fn moves_courses_to_a_day_without_them() {
+        let changes = get_monday_changes(serde_json::json!({"Zahnerhalt": "verschieben", "Zahnersatz": "verschieben"}));
+        // Zahnerhalt 7 takes place from monday to thursday, Zahnersatz 7 on every day but wednesday
+        assert!(changes.contains(&(CourseType::Zahnerhalt, 7, 0, Some(4))));
+        assert!(changes.contains(&(CourseType::Zahnersatz, 7, 0, Some(2))));
+        // Curriculum is dropped for the whole week by default
+        assert_eq!(
+            changes.iter().filter(|change| change.0 == CourseType::Curriculum).count(),
+            5
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "feiertagsRegeln: Exkurs runs every day and can not be moved")]
+    fn does_not_move_daily_courses() {
+        get_monday_changes(serde_json::json!({"Exkurs": "verschieben"}));
+    }
+
+    #[test]
+    fn reports_daily_courses_that_should_be_moved() {
+        let input = get_input(serde_json::json!({"feiertagsRegeln": {"Curriculum": "verschieben"}}));
+        assert_eq!(
+            get_problems(&input),
+            vec!["feiertagsRegeln: Curriculum runs every day and can not be moved, use ganzeWoche or nurFeiertag"
+                .to_string()]
+        );
+    }
+
+    #[test]
+    fn pairs_the_listed_students_first() {
+        let input = get_input(serde_json::json!({"zahnersatzPaare": {"aktiv": true, "paare": [[4, 1]]}}));
+        let pairs = get_pairs(&input);
+        assert_eq!(pairs[0], vec![4, 1]);
+        assert_eq!(pairs[1], vec![2, 3]);
+        assert_eq!(pairs.iter().map(Vec::len).sum::<usize>(), 26);
+    }
+
+    #[test]
+    #[should_panic(expected = "student 2 is in more than one Zahnersatz pair")]
+    fn rejects_a_student_in_two_pairs() {
+        let input = get_input(serde_json::json!({"zahnersatzPaare": {"aktiv": true, "paare": [[1, 2], [2, 3]]}}));
+        get_pairs(&input);
+    }
+
+    #[test]
+    fn reports_pairs_that_are_listed_twice() {
+        let input = get_input(serde_json::json!({"zahnersatzPaare": {"aktiv": true, "paare": [[1, 2], [2, 1]]}}));
+        assert_eq!(
+            get_problems(&input),
+            vec![
+                "student 2 is in more than one Zahnersatz pair".to_string(),
+                "student 1 is in more than one Zahnersatz pair".to_string(),
+            ]
+        );
+    }
+
+    /// cohorts of the first ten students, 1 and 2 are listed twice, 11 to 26 are missing
+    fn get_listed_input() -> JsonData {
+        get_input(serde_json::json!({
+            "zahnersatzAufteilung": {"art": "liste", "haelften": [
+                {"studenten": [1, 2, 3, 4, 5]},
+                {"studenten": [6, 7, 8, 9, 10, 1, 2]}
+            ]},
+            "studenten": [{"nummer": 26, "befreit": ["Zahnersatz"]}]
+        }))
+    }
+
+    #[test]
+    #[should_panic(expected = "student 1 is in more than one Zahnersatz cohort")]
+    fn rejects_a_student_in_two_cohorts() {
+        let input = get_listed_input();
+        get_zahnersatz_cohorts(&input, &get_students(&input));
+    }
+
+    #[test]
+    fn reports_students_in_two_cohorts_or_none() {
+        let mut expected = vec![
+            "student 1 is in more than one Zahnersatz cohort".to_string(),
+            "student 2 is in more than one Zahnersatz cohort".to_string(),
+        ];
+        // the exempt student 26 needs no cohort
+        expected.extend((11..26).map(|student| format!("student {} is in no Zahnersatz cohort", student)));
+        assert_eq!(get_problems(&get_listed_input()), expected);
+        // the curriculum groups 1 and 2 have the students 1, 7, 24, 2, 16 and 3, 26, 15, 23, 22, 5
+        let input = get_input(serde_json::json!({
+            "zahnersatzAufteilung": {"art": "curriculumGruppen", "haelften": [{"gruppen": [1, 2]}, {"gruppen": [2, 3, 4, 5]}]}
+        }));
+        let problems = get_problems(&input);
+        assert_eq!(problems.len(), 6);
+        assert!(problems.contains(&"student 26 is in more than one Zahnersatz cohort".to_string()));
+    }
+}
@@ -0,0 +1,316 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::cell::Ref;
+use std::collections::LinkedList;
+use std::rc::Rc;
+
+use types::*;
+use parser::get_seat_count;
+
+/// returns true if all participants of the group are pinned to the course
+pub fn is_pinned(group: &Group, course: &Course) -> bool {
+    let participants = group.participants.borrow();
+    let pinned = course.pinned.borrow();
+    !participants.is_empty() && participants.iter().all(|student| pinned.contains(student))
+}
+
+/// returns true if one of the participants of the group must not visit the course
+pub fn is_forbidden(group: &Group, course: &Course) -> bool {
+    let forbidden = course.forbidden.borrow();
+    group
+        .participants
+        .borrow()
+        .iter()
+        .any(|student| forbidden.contains(student))
+}
+
+/// returns the course of the given type and beginning on the given day (1-5) of the given week
+pub fn find_course(
+    weeks: &[Week],
+    week_number: u64,
+    day_number: u64,
+    course_type: CourseType,
+    beginning: u8,
+) -> Option<Ref<'_, Course>> {
+    let week = weeks.iter().find(|week| week.number == week_number)?;
+    if !(1..=5).contains(&day_number) {
+        return None;
+    }
+    Ref::filter_map(week.days[day_number as usize - 1].courses.borrow(), |courses| {
+        courses
+            .iter()
+            .find(|course| course.course_type == course_type && course.beginning == beginning)
+    })
+    .ok()
+}
+
+/// resolves the students of a pin either by the group number or by the listed students
+fn get_pinned_students(
+    parsed_data: &JsonData,
+    students: &LinkedList<Rc<Student>>,
+    pin: &JsonPin,
+) -> Result<Vec<Rc<Student>>, String> {
+    let mut numbers = pin.studenten.clone();
+    if let Some(group_number) = pin.gruppe {
+        let parsed_groups = match pin.kurs {
+            CourseType::Curriculum => &parsed_data.curriculumGruppen,
+            CourseType::Exkurs => &parsed_data.exkursGruppen,
+            _ => return Err(format!("{:?} groups can only be given by their students", pin.kurs)),
+        };
+        match parsed_groups.get((group_number as usize).wrapping_sub(1)) {
+            Some(group) => numbers.extend(group.iter()),
+            None => return Err(format!("there is no {:?} group {}", pin.kurs, group_number)),
+        }
+    }
+    if numbers.is_empty() {
+        return Err("neither a group nor students are given".to_string());
+    }
+    let mut pinned_students = Vec::new();
+    for number in numbers {
+        match students.iter().find(|student| student.number == number) {
+            Some(student) => pinned_students.push(student.clone()),
+            None => return Err(format!("there is no student {}", number)),
+        }
+    }
+    Ok(pinned_students)
+}
+
+/// The seats of a Zahnersatz course for every cohort that may use it. The seats are shared
+/// equally between all cohorts that are allowed to use the slot (or have groups pinned to
+/// the course), left over seats go to the cohorts listed first.
+/// Returns the index of every such cohort with its seats.
+pub fn get_cohort_seats(cohorts: &[Cohort], day_index: usize, course: &Course) -> Vec<(usize, u8)> {
+    let slot = JsonSlot {
+        tag: day_index as u64 + 1,
+        beginn: course.beginning,
+    };
+    let allowed: Vec<usize> = (0..cohorts.len())
+        .filter(|&index| {
+            cohorts[index].slots.is_empty()
+                || cohorts[index].slots.contains(&slot)
+                || cohorts[index].groups.iter().any(|group| is_pinned(group, course))
+        })
+        .collect();
+    let seat_count = get_seat_count(course.course_type) as usize;
+    allowed
+        .iter()
+        .enumerate()
+        .map(|(position, &index)| {
+            let mut seats = seat_count / allowed.len();
+            if position < seat_count % allowed.len() {
+                seats += 1;
+            }
+            (index, seats as u8)
+        })
+        .collect()
+}
+
+/// Applies the "fixierungen" of the input to the courses of the given weeks.
+/// A pin always covers whole groups of the rotations of its course type. Pinned groups will
+/// get their seat before all others when the course is distributed, forbidden groups
+/// will be skipped.
+/// Returns a description of every pin that can not be fulfilled.
+pub fn apply_pins(
+    parsed_data: &JsonData,
+    weeks: &[Week],
+    students: &LinkedList<Rc<Student>>,
+    all_rotations: &Rotations,
+) -> Result<(), Vec<String>> {
+    let rotations: Vec<&LinkedList<Group>> = all_rotations.named().into_iter().map(|(_, groups)| groups).collect();
+    let mut errors = Vec::new();
+    for pin in parsed_data.fixierungen.iter() {
+        let location = format!(
+            "KW {} day {} {:?} {}",
+            pin.woche, pin.tag, pin.kurs, pin.beginn
+        );
+        let course = match find_course(weeks, pin.woche, pin.tag, pin.kurs, pin.beginn) {
+            Some(course) => course,
+            None => {
+                errors.push(format!("{}: there is no such course", location));
+                continue;
+            }
+        };
+        let pinned_students = match get_pinned_students(parsed_data, students, pin) {
+            Ok(pinned_students) => pinned_students,
+            Err(error) => {
+                errors.push(format!("{}: {}", location, error));
+                continue;
+            }
+        };
+        for student in pinned_students.iter() {
+            let group = rotations
+                .iter()
+                .flat_map(|rotation| rotation.iter())
+                .find(|group| {
+                    group.group_type == pin.kurs && group.participants.borrow().contains(student)
+                });
+            match group {
+                Some(group) => {
+                    if group
+                        .participants
+                        .borrow()
+                        .iter()
+                        .any(|member| !pinned_students.contains(member))
+                    {
+                        errors.push(format!(
+                            "{}: student {} can only be pinned together with its group",
+                            location, student.number
+                        ));
+                    }
+                }
+                None => errors.push(format!(
+                    "{}: student {} is in no {:?} group",
+                    location, student.number, pin.kurs
+                )),
+            }
+        }
+        let mut list = if pin.verboten {
+            course.forbidden.borrow_mut()
+        } else {
+            course.pinned.borrow_mut()
+        };
+        for student in pinned_students {
+            if !list.contains(&student) {
+                list.push_back(student);
+            }
+        }
+    }
+    for week in weeks.iter() {
+        for (day_index, day) in week.days.iter().enumerate() {
+            let courses = day.courses.borrow();
+            let location = format!("KW {} day {}", week.number, day_index + 1);
+            for course in courses.iter() {
+                let pinned = course.pinned.borrow();
+                let forbidden = course.forbidden.borrow();
+                for student in pinned.iter().filter(|student| forbidden.contains(student)) {
+                    errors.push(format!(
+                        "{} {:?} {}: student {} is pinned and forbidden",
+                        location, course.course_type, course.beginning, student.number
+                    ));
+                }
+                // the pinned groups of every rotation have to fit into its seats
+                let rotation_seats: Vec<(String, &LinkedList<Group>, u8)> =
+                    if course.course_type == CourseType::Zahnersatz {
+                        get_cohort_seats(&all_rotations.zahnersatz, day_index, course)
+                            .into_iter()
+                            .map(|(index, seats)| {
+                                (format!(" of cohort {}", index + 1), &all_rotations.zahnersatz[index].groups, seats)
+                            })
+                            .collect()
+                    } else {
+                        rotations
+                            .iter()
+                            .filter(|groups| groups.iter().any(|group| group.group_type == course.course_type))
+                            .map(|groups| (String::new(), *groups, get_seat_count(course.course_type)))
+                            .collect()
+                    };
+                for (name, groups, seats) in rotation_seats {
+                    let pinned_groups = groups.iter().filter(|group| is_pinned(group, course)).count();
+                    if pinned_groups > seats as usize {
+                        errors.push(format!(
+                            "{} {:?} {}: {} pinned groups{} exceed the {} seats",
+                            location, course.course_type, course.beginning, pinned_groups, name, seats
+                        ));
+                    }
+                }
+            }
+            for (index, course) in courses.iter().enumerate() {
+                for other in courses.iter().skip(index + 1) {
+                    if other.beginning != course.beginning {
+                        continue;
+                    }
+                    let other_pinned = other.pinned.borrow();
+                    for student in course
+                        .pinned
+                        .borrow()
+                        .iter()
+                        .filter(|student| other_pinned.contains(student))
+                    {
+                        errors.push(format!(
+                            "{} {}: student {} is pinned to {:?} and {:?} at the same time",
+                            location,
+                            course.beginning,
+                            student.number,
+                            course.course_type,
+                            other.course_type
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use self::serde_json::Value;
+
+    use super::*;
+    use fixtures;
+    use parser::{get_holiday_changes, get_rotations, get_students, get_weeks};
+
+    /// the input of the repository with the given fixierungen
+    fn get_input(pins: Value) -> JsonData {
+        fixtures::get_input(serde_json::json!({ "fixierungen": pins }))
+    }
+
+    fn check_pins(input: &JsonData) -> Result<(), Vec<String>> {
+        let weeks = get_weeks(input, &get_holiday_changes(input));
+        let students = get_students(input);
+        let rotations = get_rotations(input, &students);
+        apply_pins(input, &weeks, &students, &rotations)
+    }
+
+    #[test]
+    fn pins_fit_into_the_seats_of_the_cohort() {
+        let input = get_input(serde_json::json!([
+            {"woche": 14, "tag": 1, "kurs": "Zahnersatz", "beginn": 7, "studenten": [1, 2, 3, 4, 5]}
+        ]));
+        assert_eq!(check_pins(&input), Ok(()));
+    }
+
+    #[test]
+    fn rejects_more_pins_than_the_cohort_has_seats() {
+        // both cohorts share the 10 seats, so the first one only has 5 of them
+        let input = get_input(serde_json::json!([
+            {"woche": 14, "tag": 1, "kurs": "Zahnersatz", "beginn": 7, "studenten": [1, 2, 3, 4, 5, 6]}
+        ]));
+        assert_eq!(
+            check_pins(&input),
+            Err(vec!["KW 14 day 1 Zahnersatz 7: 6 pinned groups of cohort 1 exceed the 5 seats".to_string()])
+        );
+    }
+
+    #[test]
+    fn rejects_more_pins_than_the_course_has_seats() {
+        let input = get_input(serde_json::json!([
+            {"woche": 14, "tag": 3, "kurs": "Curriculum", "beginn": 7, "gruppe": 1},
+            {"woche": 14, "tag": 3, "kurs": "Curriculum", "beginn": 7, "gruppe": 2}
+        ]));
+        assert_eq!(
+            check_pins(&input),
+            Err(vec!["KW 14 day 3 Curriculum 7: 2 pinned groups exceed the 1 seats".to_string()])
+        );
+    }
+}
@@ -0,0 +1,287 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use types::*;
+
+const DEFAULT_ID_COLUMN: &str = "Matrikelnummer";
+const DEFAULT_NAME_COLUMN: &str = "Name";
+const DEFAULT_CURRICULUM_COLUMN: &str = "Curriculumgruppe";
+const DEFAULT_EXKURS_COLUMN: &str = "Exkursgruppe";
+
+/// a row of the roster
+#[derive(Debug, Clone)]
+pub struct RosterStudent {
+    pub number: u64, // the number of the student in alban
+    pub id: String,  // the student ID of the university
+    pub name: String,
+    pub curriculum: Option<String>, // the label of the group in the roster
+    pub exkurs: Option<String>,
+}
+
+/// the students of a roster and the groups they form
+#[derive(Debug, Clone)]
+pub struct Roster {
+    pub students: Vec<RosterStudent>,
+    pub curriculum_groups: Vec<Vec<u64>>,
+    pub exkurs_groups: Vec<Vec<u64>>,
+    pub warnings: Vec<String>,
+}
+
+/// Splits a line at the delimiter. Fields may be quoted, quotes inside of them are doubled.
+fn split_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut characters = line.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '"' if quoted && characters.peek() == Some(&'"') => {
+                field.push('"');
+                characters.next();
+            }
+            '"' => quoted = !quoted,
+            _ if character == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(character),
+        }
+    }
+    fields.push(field);
+    fields.into_iter().map(|field| field.trim().to_string()).collect()
+}
+
+/// the exports of the campus management system use semicolons, others commas or tabs
+fn detect_delimiter(header: &str) -> char {
+    [';', '\t', ',']
+        .iter()
+        .cloned()
+        .find(|delimiter| header.contains(*delimiter))
+        .unwrap_or(';')
+}
+
+/// the column with the given header, ignoring case
+fn find_column(header: &[String], name: &str) -> Result<usize, String> {
+    header
+        .iter()
+        .position(|column| column.to_lowercase() == name.trim().to_lowercase())
+        .ok_or_else(|| format!("the roster has no column {}, only {}", name, header.join(", ")))
+}
+
+/// Groups the students by their label. Numeric labels are sorted by their value, so that
+/// group 10 follows group 9.
+fn get_groups<F: Fn(&RosterStudent) -> Option<&String>>(students: &[RosterStudent], label: F) -> Vec<Vec<u64>> {
+    let mut labels: Vec<&String> = Vec::new();
+    for student in students.iter() {
+        if let Some(group) = label(student) {
+            if !labels.contains(&group) {
+                labels.push(group);
+            }
+        }
+    }
+    labels.sort_by_key(|label| (label.parse::<u64>().unwrap_or(u64::MAX), label.to_string()));
+    labels
+        .into_iter()
+        .map(|group| {
+            students
+                .iter()
+                .filter(|student| label(student) == Some(group))
+                .map(|student| student.number)
+                .collect()
+        })
+        .collect()
+}
+
+/// Reads the students and their groups from the text of a roster. Every row is a student,
+/// the first row names the columns. Students without a group are reported as warning.
+pub fn parse_roster(text: &str, mapping: &JsonRoster) -> Result<Roster, String> {
+    let text = text.trim_start_matches('\u{feff}');
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|&(_, line)| !line.trim().is_empty());
+    let header_line = match lines.next() {
+        Some((_, line)) => line,
+        None => return Err("the roster is empty".to_string()),
+    };
+    let delimiter = mapping
+        .trennzeichen
+        .unwrap_or_else(|| detect_delimiter(header_line));
+    let header = split_line(header_line, delimiter);
+    let id_column = find_column(
+        &header,
+        mapping.kennungSpalte.as_ref().map_or(DEFAULT_ID_COLUMN, String::as_str),
+    )?;
+    // the name is only used in reports
+    let name_column = match mapping.nameSpalte {
+        Some(ref name) => Some(find_column(&header, name)?),
+        None => find_column(&header, DEFAULT_NAME_COLUMN).ok(),
+    };
+    let number_column = match mapping.nummerSpalte {
+        Some(ref number) => Some(find_column(&header, number)?),
+        None => None,
+    };
+    let curriculum_column = find_column(
+        &header,
+        mapping
+            .curriculumSpalte
+            .as_ref()
+            .map_or(DEFAULT_CURRICULUM_COLUMN, String::as_str),
+    )?;
+    let exkurs_column = find_column(
+        &header,
+        mapping.exkursSpalte.as_ref().map_or(DEFAULT_EXKURS_COLUMN, String::as_str),
+    )?;
+
+    let mut students: Vec<RosterStudent> = Vec::new();
+    let mut warnings = Vec::new();
+    for (index, line) in lines {
+        let line_number = index + 1;
+        let fields = split_line(line, delimiter);
+        let field = |column: usize| fields.get(column).cloned().unwrap_or_default();
+        let group = |column: usize| Some(field(column)).filter(|group| !group.is_empty());
+        let id = field(id_column);
+        if id.is_empty() {
+            return Err(format!("line {}: the student ID is missing", line_number));
+        }
+        if students.iter().any(|student| student.id == id) {
+            return Err(format!("line {}: student {} is listed twice", line_number, id));
+        }
+        let number = match number_column {
+            Some(column) => match field(column).parse::<u64>() {
+                Ok(number) if number > 0 => number,
+                _ => return Err(format!("line {}: {} is no student number", line_number, field(column))),
+            },
+            None => students.len() as u64 + 1,
+        };
+        if students.iter().any(|student| student.number == number) {
+            return Err(format!("line {}: the number {} is used twice", line_number, number));
+        }
+        let student = RosterStudent {
+            number,
+            id,
+            name: name_column.map(&field).unwrap_or_default(),
+            curriculum: group(curriculum_column),
+            exkurs: group(exkurs_column),
+        };
+        if student.curriculum.is_none() {
+            warnings.push(format!("student {} ({}) has no Curriculum group", student.number, student.id));
+        }
+        if student.exkurs.is_none() {
+            warnings.push(format!("student {} ({}) has no Exkurs group", student.number, student.id));
+        }
+        students.push(student);
+    }
+    Ok(Roster {
+        curriculum_groups: get_groups(&students, |student| student.curriculum.as_ref()),
+        exkurs_groups: get_groups(&students, |student| student.exkurs.as_ref()),
+        students,
+        warnings,
+    })
+}
+
+/// reads a roster file with the columns of the mapping
+pub fn read_roster(path: &Path, mapping: &JsonRoster) -> Roster {
+    let mut bytes = Vec::new();
+    if let Err(error) = File::open(path).and_then(|mut file| file.read_to_end(&mut bytes)) {
+        panic!("Unable to read roster {}: {}", path.display(), error);
+    }
+    let (text, is_latin1) = decode_roster(bytes);
+    match parse_roster(&text, mapping) {
+        Err(error) => panic!("Unable to parse roster {}: {}", path.display(), error),
+        Ok(mut roster) => {
+            if is_latin1 {
+                roster.warnings.insert(
+                    0,
+                    format!(
+                        "{} is not UTF-8 and was read as Latin-1, export it as \"CSV UTF-8\" to be sure of the names",
+                        path.display()
+                    ),
+                );
+            }
+            roster
+        }
+    }
+}
+
+/// Decodes the bytes of a roster as UTF-8. Spreadsheets on Windows export CSV in
+/// Windows-1252, which has the same umlauts as Latin-1, so such files are read as Latin-1.
+/// Returns the text and whether it was read as Latin-1.
+fn decode_roster(bytes: Vec<u8>) -> (String, bool) {
+    match String::from_utf8(bytes) {
+        Ok(text) => (text, false),
+        Err(error) => (error.into_bytes().iter().map(|&byte| byte as char).collect(), true),
+    }
+}
+
+/// Replaces the groups of the input with the ones of the roster. The number of students
+/// has to match, otherwise the groups would refer to students that do not exist.
+pub fn apply_roster(parsed_data: &mut JsonData, roster: &Roster) -> Result<(), String> {
+    let highest = roster.students.iter().map(|student| student.number).max().unwrap_or(0);
+    if highest > parsed_data.studentenAnzahl {
+        return Err(format!(
+            "the roster has student {}, but studentenAnzahl is {}",
+            highest, parsed_data.studentenAnzahl
+        ));
+    }
+    parsed_data.curriculumGruppen = roster.curriculum_groups.clone();
+    parsed_data.exkursGruppen = roster.exkurs_groups.clone();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn get_mapping() -> JsonRoster {
+        JsonRoster {
+            datei: "roster.csv".to_string(),
+            trennzeichen: Some(';'),
+            kennungSpalte: None,
+            nameSpalte: None,
+            nummerSpalte: None,
+            curriculumSpalte: None,
+            exkursSpalte: None,
+        }
+    }
+
+    #[test]
+    fn reads_rosters_exported_as_latin1() {
+        // "Müller" and "Jörg" in Windows-1252
+        let mut bytes = b"Matrikelnummer;Name;Curriculumgruppe;Exkursgruppe\n1;M".to_vec();
+        bytes.extend_from_slice(&[0xfc]);
+        bytes.extend_from_slice(b"ller;A;1\n2;J");
+        bytes.extend_from_slice(&[0xf6]);
+        bytes.extend_from_slice(b"rg;A;1\n");
+        let path = std::env::temp_dir().join(format!("alban-roster-{}.csv", std::process::id()));
+        fs::write(&path, bytes).unwrap();
+        let roster = read_roster(&path, &get_mapping());
+        fs::remove_file(&path).unwrap();
+        let names: Vec<&str> = roster.students.iter().map(|student| student.name.as_str()).collect();
+        assert_eq!(names, vec!["Müller", "Jörg"]);
+        assert_eq!(roster.curriculum_groups, vec![vec![1, 2]]);
+        assert!(roster.warnings[0].contains("read as Latin-1"), "{:?}", roster.warnings);
+    }
+
+    #[test]
+    fn reads_rosters_in_utf8_without_warning() {
+        let (text, is_latin1) = decode_roster("1;Müller".as_bytes().to_vec());
+        assert_eq!(text, "1;Müller");
+        assert!(!is_latin1);
+    }
+}
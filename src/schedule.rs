@@ -0,0 +1,303 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+extern crate serde_json;
+
+use std::collections::LinkedList;
+use std::fs::File;
+use std::rc::Rc;
+
+use types::*;
+use legacy::read_text_schedule;
+
+/// Reads a distribution that was exported as json before. Files ending with ".txt" are read
+/// as the text output instead.
+pub fn read_schedule(path: &str) -> JsonSchedule {
+    if path.ends_with(".txt") {
+        return read_text_schedule(path);
+    }
+    match File::open(path) {
+        Err(error) => panic!("Unable to open schedule {}: {}", path, error),
+        Ok(file) => match serde_json::from_reader(file) {
+            Err(error) => panic!("Unable to parse schedule {}: {}", path, error),
+            Ok(schedule) => schedule,
+        },
+    }
+}
+
+/// writes the distribution as json, so that it can be continued later
+pub fn write_schedule(path: &str, schedule: &JsonSchedule) {
+    match File::create(path) {
+        Err(error) => println!("couldn't create {}: {}", path, error),
+        Ok(file) => {
+            if let Err(error) = serde_json::to_writer_pretty(file, schedule) {
+                println!("Unable to write schedule to file: {}", error);
+            }
+        }
+    }
+}
+
+fn get_numbers(students: &LinkedList<Rc<Student>>) -> Vec<u64> {
+    students.iter().map(|student| student.number).collect()
+}
+
+/// the current order and session count of all rotations
+pub fn get_rotation_states(rotations: &Rotations) -> Vec<JsonRotation> {
+    rotations
+        .named()
+        .into_iter()
+        .map(|(name, groups)| JsonRotation {
+            name,
+            gruppen: groups
+                .iter()
+                .map(|group| JsonRotationGroup {
+                    studenten: get_numbers(&group.participants.borrow()),
+                    sitzungen: group.sessions.get(),
+                    ausgefallen: group.lost.get(),
+                    nachgeholt: group.made_up.get(),
+                    konflikte: group.conflicts.get(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Brings the rotations into the order of the given states and takes over the session counts
+/// if they are kept (they are not for a new semester).
+/// A group takes the place of the first state that shares a student with it, so groups that
+/// lost or gained students are still recognized. Groups without a state stay at the end.
+pub fn restore_rotations(states: &[JsonRotation], rotations: &mut Rotations, keep_counts: bool) {
+    for (name, groups) in rotations.named_mut() {
+        let state = match states.iter().find(|state| state.name == name) {
+            Some(state) => state,
+            None => {
+                println!("Warning: there is no state for the {} rotation", name);
+                continue;
+            }
+        };
+        let mut unmatched = LinkedList::new();
+        unmatched.append(groups);
+        for state_group in state.gruppen.iter() {
+            let position = unmatched.iter().position(|group| {
+                group
+                    .participants
+                    .borrow()
+                    .iter()
+                    .any(|student| state_group.studenten.contains(&student.number))
+            });
+            if let Some(position) = position {
+                let mut rest = unmatched.split_off(position);
+                if let Some(group) = rest.pop_front() {
+                    if keep_counts {
+                        group.sessions.set(state_group.sitzungen);
+                        group.lost.set(state_group.ausgefallen);
+                        group.made_up.set(state_group.nachgeholt);
+                        group.conflicts.set(state_group.konflikte);
+                    }
+                    groups.push_back(group);
+                }
+                unmatched.append(&mut rest);
+            }
+        }
+        groups.append(&mut unmatched);
+    }
+}
+
+/// Takes over the participants of an already distributed week.
+/// Students that are unknown by now and courses that do not exist anymore will be reported
+/// and skipped.
+pub fn restore_week(week: &Week, scheduled: &JsonScheduleWeek, students: &LinkedList<Rc<Student>>) {
+    let find_students = |numbers: &Vec<u64>| -> LinkedList<Rc<Student>> {
+        let mut found = LinkedList::new();
+        for number in numbers {
+            match students.iter().find(|student| student.number == *number) {
+                Some(student) => found.push_back(student.clone()),
+                None => println!(
+                    "Warning: student {} of KW {} is unknown",
+                    number, week.number
+                ),
+            }
+        }
+        found
+    };
+    for scheduled_course in scheduled.kurse.iter() {
+        let day = match week.days.get((scheduled_course.tag as usize).wrapping_sub(1)) {
+            Some(day) => day,
+            None => continue,
+        };
+        let courses = day.courses.borrow();
+        match courses.iter().find(|course| {
+            course.course_type == scheduled_course.kurs && course.beginning == scheduled_course.beginn
+        }) {
+            Some(course) => {
+                *course.participants.borrow_mut() = find_students(&scheduled_course.teilnehmer);
+                *course.operators.borrow_mut() = find_students(&scheduled_course.behandler);
+            }
+            None => println!(
+                "Warning: KW {} day {} {:?} {} does not exist anymore",
+                week.number, scheduled_course.tag, scheduled_course.kurs, scheduled_course.beginn
+            ),
+        }
+    }
+}
+
+/// Converts the distributed weeks into the json export. The rotation states are expected
+/// in the order of the weeks.
+pub fn get_schedule(
+    weeks: &[Week],
+    week_states: Vec<Vec<JsonRotation>>,
+    final_state: Vec<JsonRotation>,
+) -> JsonSchedule {
+    let mut scheduled_weeks = Vec::new();
+    for (week, rotations) in weeks.iter().zip(week_states) {
+        let mut courses = Vec::new();
+        for (day_index, day) in week.days.iter().enumerate() {
+            for course in day.courses.borrow().iter() {
+                courses.push(JsonScheduleCourse {
+                    tag: day_index as u64 + 1,
+                    kurs: course.course_type,
+                    beginn: course.beginning,
+                    teilnehmer: get_numbers(&course.participants.borrow()),
+                    behandler: get_numbers(&course.operators.borrow()),
+                });
+            }
+        }
+        scheduled_weeks.push(JsonScheduleWeek {
+            nummer: week.number,
+            rotationen: rotations,
+            kurse: courses,
+        });
+    }
+    JsonSchedule {
+        wochen: scheduled_weeks,
+        rotationen: final_state,
+    }
+}
+
+/// the courses of a student in the order of their time
+pub fn get_timetable(schedule: &JsonSchedule, student: u64) -> JsonTimetable {
+    let mut courses = Vec::new();
+    let mut operated = Vec::new();
+    for week in schedule.wochen.iter() {
+        for course in week.kurse.iter() {
+            let slot = JsonCourseSlot {
+                woche: week.nummer,
+                tag: course.tag,
+                kurs: course.kurs,
+                beginn: course.beginn,
+            };
+            if course.teilnehmer.contains(&student) {
+                courses.push(slot);
+            }
+            if course.behandler.contains(&student) {
+                operated.push(slot);
+            }
+        }
+    }
+    courses.sort_by_key(|slot| (slot.woche, slot.tag, slot.beginn));
+    operated.sort_by_key(|slot| (slot.woche, slot.tag, slot.beginn));
+    JsonTimetable {
+        nummer: student,
+        termine: courses,
+        behandler: operated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use self::serde_json::Value;
+
+    use super::*;
+    use distribution::Options;
+    use fixtures::{distribute_quietly, get_input};
+
+    /// the order of the students in every rotation
+    fn get_order(states: &[JsonRotation]) -> Vec<Vec<Vec<u64>>> {
+        states
+            .iter()
+            .map(|state| state.gruppen.iter().map(|group| group.studenten.clone()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_weeks_before_the_given_one() {
+        let previous = distribute_quietly(&get_input(serde_json::json!({})), Options::default()).schedule;
+        // student 5 leaves after KW 17
+        let data = get_input(serde_json::json!({"studenten": [{"nummer": 5, "bis": 17}]}));
+        let options = Options {
+            from_week: Some(18),
+            previous: Some(previous.clone()),
+            ..Options::default()
+        };
+        let schedule = distribute_quietly(&data, options).schedule;
+        let kept = |schedule: &JsonSchedule| -> Value {
+            serde_json::to_value(&schedule.wochen[..6]).unwrap()
+        };
+        assert_eq!(schedule.wochen[6].nummer, 18);
+        assert_eq!(kept(&schedule), kept(&previous));
+        // the rotations continue where they were in KW 18
+        assert_eq!(
+            serde_json::to_value(&schedule.wochen[6].rotationen).unwrap(),
+            serde_json::to_value(&previous.wochen[6].rotationen).unwrap()
+        );
+        assert!(schedule.wochen[6..]
+            .iter()
+            .flat_map(|week| week.kurse.iter())
+            .all(|course| !course.teilnehmer.contains(&5)));
+    }
+
+    #[test]
+    fn the_same_seed_results_in_the_same_order() {
+        let get_schedule = |seed: u64| {
+            distribute_quietly(&get_input(serde_json::json!({"rotationsStartwert": seed})), Options::default())
+                .schedule
+        };
+        let unshuffled = distribute_quietly(&get_input(serde_json::json!({})), Options::default()).schedule;
+        let unshuffled = get_order(&unshuffled.wochen[0].rotationen);
+        let first = get_schedule(7);
+        assert_eq!(
+            serde_json::to_value(&first).unwrap(),
+            serde_json::to_value(get_schedule(7)).unwrap()
+        );
+        let order = get_order(&first.wochen[0].rotationen);
+        assert_ne!(order, unshuffled);
+        assert_ne!(order, get_order(&get_schedule(8).wochen[0].rotationen));
+        // only the order changes, not the groups
+        for (shuffled, groups) in order.iter().zip(unshuffled.iter()) {
+            assert_eq!(shuffled.len(), groups.len());
+            assert!(groups.iter().all(|group| shuffled.contains(group)));
+        }
+    }
+
+    #[test]
+    fn continues_the_order_of_the_previous_semester() {
+        let previous = distribute_quietly(&get_input(serde_json::json!({})), Options::default()).schedule;
+        let options = Options {
+            previous_semester: Some(previous.clone()),
+            ..Options::default()
+        };
+        let schedule = distribute_quietly(&get_input(serde_json::json!({})), options).schedule;
+        let first = &schedule.wochen[0].rotationen;
+        assert_eq!(get_order(first), get_order(&previous.rotationen));
+        // the session counts start anew
+        assert!(first
+            .iter()
+            .flat_map(|state| state.gruppen.iter())
+            .all(|group| group.sitzungen == 0));
+    }
+}
@@ -0,0 +1,736 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+extern crate serde_json;
+
+use std::fs::File;
+
+use self::serde_json::{Map, Value};
+
+use types::*;
+
+/// A key of an input object. The English alias has to match the serde alias of the field
+/// in types.rs, the tests compare both.
+struct Key {
+    name: &'static str,
+    alias: Option<&'static str>,
+    required: bool,
+    schema: Value,
+}
+
+fn key(name: &'static str, alias: &'static str, schema: Value) -> Key {
+    Key {
+        name,
+        alias: Some(alias),
+        required: false,
+        schema,
+    }
+}
+
+fn required_key(name: &'static str, alias: &'static str, schema: Value) -> Key {
+    Key {
+        name,
+        alias: Some(alias),
+        required: true,
+        schema,
+    }
+}
+
+/// keys like the course types that are the same in both languages
+fn plain_key(name: &'static str, schema: Value) -> Key {
+    Key {
+        name,
+        alias: None,
+        required: false,
+        schema,
+    }
+}
+
+fn described(kind: &str, description: &str) -> Map<String, Value> {
+    let mut schema = Map::new();
+    schema.insert("type".to_string(), Value::from(kind));
+    schema.insert("description".to_string(), Value::from(description));
+    schema
+}
+
+fn integer(description: &str) -> Value {
+    let mut schema = described("integer", description);
+    schema.insert("minimum".to_string(), Value::from(0));
+    Value::Object(schema)
+}
+
+fn boolean(description: &str) -> Value {
+    Value::Object(described("boolean", description))
+}
+
+fn string(description: &str) -> Value {
+    Value::Object(described("string", description))
+}
+
+fn values(description: &str, values: &[&str]) -> Value {
+    let mut schema = described("string", description);
+    schema.insert(
+        "enum".to_string(),
+        Value::Array(values.iter().map(|&value| Value::from(value)).collect()),
+    );
+    Value::Object(schema)
+}
+
+fn array(description: &str, items: Value) -> Value {
+    let mut schema = described("array", description);
+    schema.insert("items".to_string(), items);
+    Value::Object(schema)
+}
+
+/// An object that only allows the given keys. The English alias may be used instead of
+/// a required key.
+fn object(description: &str, keys: Vec<Key>) -> Value {
+    let mut schema = described("object", description);
+    let mut properties = Map::new();
+    let mut required = Vec::new();
+    for key in keys {
+        if let Some(alias) = key.alias {
+            let mut schema = key.schema.clone();
+            if let Some(description) = schema.get_mut("description") {
+                *description = Value::from(format!(
+                    "{} (English alias of {})",
+                    description.as_str().unwrap_or(""),
+                    key.name
+                ));
+            }
+            properties.insert(alias.to_string(), schema);
+        }
+        if key.required {
+            let names: Vec<Value> = Some(key.name)
+                .into_iter()
+                .chain(key.alias)
+                .map(|name| {
+                    let mut choice = Map::new();
+                    choice.insert("required".to_string(), Value::Array(vec![Value::from(name)]));
+                    Value::Object(choice)
+                })
+                .collect();
+            let mut choices = Map::new();
+            choices.insert("oneOf".to_string(), Value::Array(names));
+            required.push(Value::Object(choices));
+        }
+        properties.insert(key.name.to_string(), key.schema);
+    }
+    schema.insert("properties".to_string(), Value::Object(properties));
+    if !required.is_empty() {
+        schema.insert("allOf".to_string(), Value::Array(required));
+    }
+    schema.insert("additionalProperties".to_string(), Value::from(false));
+    Value::Object(schema)
+}
+
+fn course_type(description: &str) -> Value {
+    values(description, &["Curriculum", "Exkurs", "Zahnerhalt", "Zahnersatz"])
+}
+
+fn groups(description: &str) -> Value {
+    array(
+        description,
+        array("the numbers of the students of the group", integer("a student")),
+    )
+}
+
+fn group_rules(course_type: &str, size: u64) -> Value {
+    object(
+        &format!("the rules for the {} groups", course_type),
+        vec![
+            key(
+                "groesse",
+                "size",
+                integer(&format!("the minimum size of the groups (default {}), they differ by at most one", size)),
+            ),
+            key("zusammen", "together", groups("students that have to be in the same group")),
+            key("getrennt", "apart", groups("students that have to be in different groups")),
+        ],
+    )
+}
+
+fn slot() -> Value {
+    object(
+        "a weekly recurring course slot",
+        vec![
+            required_key("tag", "day", integer("the day of the course (1-5)")),
+            required_key("beginn", "beginning", integer("the beginning of the course (7 or 16)")),
+        ],
+    )
+}
+
+fn course_slot(description: &str) -> Value {
+    object(
+        description,
+        vec![
+            required_key("woche", "week", integer("the week of the course")),
+            required_key("tag", "day", integer("the day of the course (1-5)")),
+            required_key("kurs", "course", course_type("the course type")),
+            required_key("beginn", "beginning", integer("the beginning of the course (7 or 16)")),
+        ],
+    )
+}
+
+fn per_course_type<F: Fn(&str) -> Value>(description: &str, value: F) -> Value {
+    object(
+        description,
+        COURSE_TYPES
+            .iter()
+            .map(|course_type| {
+                let name = match *course_type {
+                    CourseType::Curriculum => "Curriculum",
+                    CourseType::Exkurs => "Exkurs",
+                    CourseType::Zahnerhalt => "Zahnerhalt",
+                    CourseType::Zahnersatz => "Zahnersatz",
+                };
+                plain_key(name, value(name))
+            })
+            .collect(),
+    )
+}
+
+/// Returns the JSON Schema of the input with a description of every key, for editors to
+/// validate and complete the input.
+pub fn get_schema() -> Value {
+    let mut schema = match object(
+        "The constraints of the distribution of alban",
+        vec![
+            plain_key("$schema", string("the schema of the input, for editors")),
+            plain_key("version", integer("the version of the input format")),
+            required_key(
+                "studentenAnzahl",
+                "studentCount",
+                integer("the total count of students that should be distributed"),
+            ),
+            required_key(
+                "wochen",
+                "weeks",
+                object(
+                    "the weeks of the semester",
+                    vec![
+                        required_key("kwAnfang", "firstWeek", integer("the number of the first week")),
+                        required_key(
+                            "kwEnde",
+                            "lastWeek",
+                            integer("the number of the last week, greater than the first"),
+                        ),
+                    ],
+                ),
+            ),
+            required_key(
+                "feiertage",
+                "holidays",
+                array(
+                    "the holidays of the semester",
+                    object(
+                        "the holidays of a week",
+                        vec![
+                            required_key("woche", "week", integer("the week of the holidays")),
+                            required_key(
+                                "tage",
+                                "days",
+                                array("the days that are holidays (1-5)", integer("a day")),
+                            ),
+                            key(
+                                "kurse",
+                                "courses",
+                                array(
+                                    "the holiday only covers these course types, all if omitted",
+                                    course_type("a course type"),
+                                ),
+                            ),
+                            key(
+                                "ab",
+                                "from",
+                                integer("the holiday only covers courses beginning at or after this hour"),
+                            ),
+                            key(
+                                "bis",
+                                "until",
+                                integer("the holiday only covers courses beginning before this hour"),
+                            ),
+                        ],
+                    ),
+                ),
+            ),
+            key(
+                "exkursGruppen",
+                "exkursGroups",
+                groups("the groups for the Exkurs courses, required without gruppenImport"),
+            ),
+            key(
+                "curriculumGruppen",
+                "curriculumGroups",
+                groups("the groups for the Curriculum courses, required without gruppenImport"),
+            ),
+            key(
+                "gruppenImport",
+                "rosterImport",
+                object(
+                    "reads the groups from a roster of the campus management system (csv) with a header line",
+                    vec![
+                        required_key("datei", "file", string("the roster, relative to the input")),
+                        key(
+                            "trennzeichen",
+                            "delimiter",
+                            string("the delimiter of the columns, detected from the header if omitted"),
+                        ),
+                        key("kennungSpalte", "idColumn", string("the column of the student IDs (Matrikelnummer)")),
+                        key("nameSpalte", "nameColumn", string("the column of the names (Name)")),
+                        key(
+                            "nummerSpalte",
+                            "numberColumn",
+                            string("the column of the numbers of the students, the rows are numbered if omitted"),
+                        ),
+                        key(
+                            "curriculumSpalte",
+                            "curriculumColumn",
+                            string("the column of the Curriculum groups (Curriculumgruppe)"),
+                        ),
+                        key("exkursSpalte", "exkursColumn", string("the column of the Exkurs groups (Exkursgruppe)")),
+                    ],
+                ),
+            ),
+            key(
+                "gruppenBildung",
+                "groupFormation",
+                object(
+                    "how alban groups forms the Curriculum and Exkurs groups",
+                    vec![
+                        plain_key("curriculum", group_rules("Curriculum", 5)),
+                        plain_key("exkurs", group_rules("Exkurs", 2)),
+                        key(
+                            "verschachtelt",
+                            "nested",
+                            boolean("the Exkurs groups are formed inside of the Curriculum groups (default true)"),
+                        ),
+                        key("startwert", "seed", integer("shuffles the students before they are grouped")),
+                    ],
+                ),
+            ),
+            key(
+                "zahnersatzAufteilung",
+                "zahnersatzSplit",
+                object(
+                    "how the students are split into the cohorts that share the Zahnersatz courses",
+                    vec![
+                        key(
+                            "art",
+                            "method",
+                            values(
+                                "position: consecutive parts of the student list, geradeUngerade (oddEven): \
+                                 odd and even numbers, liste (list): the students of the cohorts, \
+                                 curriculumGruppen (curriculumGroups): the curriculum groups of the cohorts",
+                                &[
+                                    "position",
+                                    "geradeUngerade",
+                                    "oddEven",
+                                    "liste",
+                                    "list",
+                                    "curriculumGruppen",
+                                    "curriculumGroups",
+                                ],
+                            ),
+                        ),
+                        key(
+                            "haelften",
+                            "cohorts",
+                            array(
+                                "one object per cohort, two cohorts if omitted",
+                                object(
+                                    "a cohort",
+                                    vec![
+                                        key(
+                                            "studenten",
+                                            "students",
+                                            array("the students of the cohort (only for liste)", integer("a student")),
+                                        ),
+                                        key(
+                                            "gruppen",
+                                            "groups",
+                                            array(
+                                                "the curriculum groups of the cohort, starting with 1 \
+                                                 (only for curriculumGruppen)",
+                                                integer("a group"),
+                                            ),
+                                        ),
+                                        key(
+                                            "termine",
+                                            "slots",
+                                            array("the weekly slots the cohort may use, all if omitted", slot()),
+                                        ),
+                                    ],
+                                ),
+                            ),
+                        ),
+                    ],
+                ),
+            ),
+            key(
+                "zahnersatzPaare",
+                "zahnersatzPairs",
+                object(
+                    "Zahnersatz students work in pairs, one treats and the other assists",
+                    vec![
+                        key("aktiv", "active", boolean("enables the pair mode")),
+                        key(
+                            "paare",
+                            "pairs",
+                            groups("fixed pairs of two students, the others are paired in order"),
+                        ),
+                    ],
+                ),
+            ),
+            key(
+                "fixierungen",
+                "pins",
+                array(
+                    "pins groups to a course or forbids them to visit it",
+                    object(
+                        "a pinned or forbidden course",
+                        vec![
+                            required_key("woche", "week", integer("the week of the course")),
+                            required_key("tag", "day", integer("the day of the course (1-5)")),
+                            required_key("kurs", "course", course_type("the course type")),
+                            required_key("beginn", "beginning", integer("the beginning of the course (7 or 16)")),
+                            key(
+                                "gruppe",
+                                "group",
+                                integer("the number of the group in curriculumGruppen or exkursGruppen, starting with 1"),
+                            ),
+                            key(
+                                "studenten",
+                                "students",
+                                array("the students whose groups are pinned", integer("a student")),
+                            ),
+                            key("verboten", "forbidden", boolean("forbid the course instead of pinning it")),
+                        ],
+                    ),
+                ),
+            ),
+            key(
+                "studenten",
+                "students",
+                array(
+                    "students that are not enrolled for the whole semester or whose quotas differ",
+                    object(
+                        "the enrollment and the quotas of a student",
+                        vec![
+                            required_key("nummer", "number", integer("the number of the student")),
+                            key("von", "from", integer("the first week the student is enrolled")),
+                            key("bis", "until", integer("the last week the student is enrolled")),
+                            key(
+                                "zusaetzlich",
+                                "extra",
+                                per_course_type("sessions on top of the quotas", |name| {
+                                    integer(&format!("the extra {} sessions of the student", name))
+                                }),
+                            ),
+                            key(
+                                "absolviert",
+                                "completed",
+                                per_course_type("sessions of earlier semesters that count towards the quotas", |name| {
+                                    integer(&format!("the completed {} sessions of the student", name))
+                                }),
+                            ),
+                            key(
+                                "befreit",
+                                "exempt",
+                                array("the course types the student does not visit", course_type("a course type")),
+                            ),
+                        ],
+                    ),
+                ),
+            ),
+            key(
+                "kontingente",
+                "quotas",
+                per_course_type(
+                    "the sessions every student should visit during the semester",
+                    |name| integer(&format!("the {} sessions of every student", name)),
+                ),
+            ),
+            key(
+                "feiertagsRegeln",
+                "holidayRules",
+                per_course_type(
+                    "what happens to the courses in a week with a holiday",
+                    |name| {
+                        values(
+                            &format!(
+                                "ganzeWoche (wholeWeek): the {} course is dropped for the whole week, \
+                                 nurFeiertag (holidayOnly): only on the holiday, verschieben (move): it is \
+                                 moved to another day (not for Curriculum and Exkurs, they run every day)",
+                                name
+                            ),
+                            &["ganzeWoche", "wholeWeek", "nurFeiertag", "holidayOnly", "verschieben", "move"],
+                        )
+                    },
+                ),
+            ),
+            key(
+                "nachholen",
+                "makeUp",
+                object(
+                    "compensates the sessions that were dropped because of holidays",
+                    vec![
+                        required_key(
+                            "art",
+                            "method",
+                            values(
+                                "vorrang (priority): the next free seats of the course type, termine (slots): \
+                                 the make-up courses",
+                                &["vorrang", "priority", "termine", "slots"],
+                            ),
+                        ),
+                        key(
+                            "termine",
+                            "slots",
+                            array("the make-up courses", course_slot("a make-up course")),
+                        ),
+                    ],
+                ),
+            ),
+            key(
+                "rotationsStartwert",
+                "rotationSeed",
+                integer("shuffles the initial order of all rotations, the same value results in the same order"),
+            ),
+            key(
+                "reihenfolge",
+                "order",
+                object(
+                    "the order the course types are distributed in every day",
+                    vec![
+                        required_key(
+                            "kurse",
+                            "courses",
+                            array("all four course types, each exactly once", course_type("a course type")),
+                        ),
+                        key("rotieren", "rotate", boolean("every week the next course type goes first")),
+                    ],
+                ),
+            ),
+        ],
+    ) {
+        Value::Object(schema) => schema,
+        _ => Map::new(),
+    };
+    let mut header = Map::new();
+    header.insert(
+        "$schema".to_string(),
+        Value::from("http://json-schema.org/draft-07/schema#"),
+    );
+    header.insert("title".to_string(), Value::from(format!("alban input version {}", INPUT_VERSION)));
+    header.append(&mut schema);
+    Value::Object(header)
+}
+
+/// writes the schema of the input
+pub fn write_schema(path: &str) {
+    let file = match File::create(path) {
+        Err(error) => panic!("Unable to create {}: {}", path, error),
+        Ok(file) => file,
+    };
+    if let Err(error) = serde_json::to_writer_pretty(file, &get_schema()) {
+        panic!("Unable to write {}: {}", path, error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use migrate::get_unknown_keys;
+
+    fn slot() -> JsonCourseSlot {
+        JsonCourseSlot {
+            woche: 14,
+            tag: 2,
+            kurs: CourseType::Zahnerhalt,
+            beginn: 7,
+        }
+    }
+
+    fn quotas() -> JsonQuotas {
+        JsonQuotas {
+            Curriculum: Some(1),
+            Exkurs: Some(1),
+            Zahnerhalt: Some(1),
+            Zahnersatz: Some(1),
+        }
+    }
+
+    fn group_rules() -> JsonGroupRules {
+        JsonGroupRules {
+            groesse: Some(5),
+            zusammen: vec![vec![1, 2]],
+            getrennt: vec![vec![3, 4]],
+        }
+    }
+
+    /// An input with every key of the input format. New fields of the input types have to
+    /// be added here, so the test fails until the schema knows them as well.
+    fn get_full_input() -> JsonData {
+        JsonData {
+            schema: Some("./input.schema.json".to_string()),
+            version: Some(INPUT_VERSION),
+            studentenAnzahl: 4,
+            wochen: JsonWeeks { kwAnfang: 12, kwEnde: 25 },
+            feiertage: vec![JsonHoliday {
+                woche: 12,
+                tage: vec![1],
+                kurse: vec![CourseType::Curriculum],
+                ab: Some(7),
+                bis: Some(16),
+            }],
+            exkursGruppen: vec![vec![1, 2], vec![3, 4]],
+            curriculumGruppen: vec![vec![1, 2, 3, 4]],
+            gruppenImport: Some(JsonRoster {
+                datei: "roster.csv".to_string(),
+                trennzeichen: Some(';'),
+                kennungSpalte: Some("Matrikelnummer".to_string()),
+                nameSpalte: Some("Name".to_string()),
+                nummerSpalte: Some("Nummer".to_string()),
+                curriculumSpalte: Some("Curriculumgruppe".to_string()),
+                exkursSpalte: Some("Exkursgruppe".to_string()),
+            }),
+            gruppenBildung: Some(JsonGrouping {
+                curriculum: group_rules(),
+                exkurs: group_rules(),
+                verschachtelt: Some(true),
+                startwert: Some(1),
+            }),
+            zahnersatzAufteilung: JsonCohortSplit {
+                art: JsonSplitMethod::Liste,
+                haelften: vec![JsonCohort {
+                    studenten: vec![1, 2],
+                    gruppen: vec![1],
+                    termine: vec![JsonSlot { tag: 1, beginn: 7 }],
+                }],
+            },
+            zahnersatzPaare: JsonPairs {
+                aktiv: true,
+                paare: vec![vec![1, 2]],
+            },
+            fixierungen: vec![JsonPin {
+                woche: 14,
+                tag: 2,
+                kurs: CourseType::Curriculum,
+                beginn: 7,
+                gruppe: Some(1),
+                studenten: vec![1],
+                verboten: true,
+            }],
+            studenten: vec![JsonStudent {
+                nummer: 1,
+                von: Some(12),
+                bis: Some(20),
+                zusaetzlich: quotas(),
+                absolviert: quotas(),
+                befreit: vec![CourseType::Exkurs],
+            }],
+            kontingente: quotas(),
+            feiertagsRegeln: JsonHolidayRules {
+                Curriculum: Some(JsonHolidayRule::GanzeWoche),
+                Exkurs: Some(JsonHolidayRule::NurFeiertag),
+                Zahnerhalt: Some(JsonHolidayRule::Verschieben),
+                Zahnersatz: Some(JsonHolidayRule::NurFeiertag),
+            },
+            nachholen: Some(JsonMakeUp {
+                art: JsonMakeUpMethod::Termine,
+                termine: vec![slot()],
+            }),
+            rotationsStartwert: Some(1),
+            reihenfolge: Some(JsonOrder {
+                kurse: COURSE_TYPES.to_vec(),
+                rotieren: true,
+            }),
+        }
+    }
+
+    /// The keys or values serde accepts at the probe, English aliases included. They are
+    /// listed in the error of the probe.
+    fn get_accepted(input: &Value) -> Vec<String> {
+        let error = match serde_json::from_value::<JsonData>(input.clone()) {
+            Ok(_) => panic!("the probe was accepted"),
+            Err(error) => error.to_string(),
+        };
+        let expected = match error.find("expected") {
+            Some(position) => &error[position..],
+            None => panic!("{}", error),
+        };
+        let mut names: Vec<String> = expected.split('`').skip(1).step_by(2).map(String::from).collect();
+        names.sort();
+        names
+    }
+
+    /// Compares the keys and values of the schema at every object and string of the input
+    /// with the ones serde accepts there.
+    fn compare_names(input: &Value, pointer: &str, schema: &Value, differences: &mut Vec<String>) {
+        match input.pointer(pointer) {
+            Some(Value::Object(object)) => {
+                for key in object.keys() {
+                    let child = format!("{}/{}", pointer, key);
+                    compare_names(input, &child, &schema["properties"][key.as_str()], differences);
+                }
+            }
+            Some(Value::Array(array)) => {
+                for index in 0..array.len() {
+                    compare_names(input, &format!("{}/{}", pointer, index), &schema["items"], differences);
+                }
+            }
+            _ => {}
+        }
+        let mut probe = input.clone();
+        let mut names: Vec<String> = match probe.pointer_mut(pointer) {
+            Some(Value::Object(object)) => {
+                object.insert("?".to_string(), Value::Null);
+                schema["properties"]
+                    .as_object()
+                    .map_or_else(Vec::new, |properties| properties.keys().cloned().collect())
+            }
+            Some(value) if value.is_string() && schema.get("enum").is_some() => {
+                *value = Value::from("?");
+                schema["enum"].as_array().map_or_else(Vec::new, |values| {
+                    values.iter().filter_map(Value::as_str).map(String::from).collect()
+                })
+            }
+            _ => return,
+        };
+        names.sort();
+        let accepted = get_accepted(&probe);
+        if names != accepted {
+            differences.push(format!("{}: the schema has {:?}, serde accepts {:?}", pointer, names, accepted));
+        }
+    }
+
+    #[test]
+    fn knows_every_key_of_the_input_types() {
+        let input = serde_json::to_value(get_full_input()).unwrap();
+        assert_eq!(get_unknown_keys(&input), Vec::<String>::new());
+        // and the input is read back
+        serde_json::from_value::<JsonData>(input).unwrap();
+    }
+
+    #[test]
+    fn has_the_english_aliases_of_the_input_types() {
+        let input = serde_json::to_value(get_full_input()).unwrap();
+        let mut differences = Vec::new();
+        compare_names(&input, "", &get_schema(), &mut differences);
+        assert!(differences.is_empty(), "{:#?}", differences);
+    }
+}
@@ -0,0 +1,443 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+extern crate serde;
+extern crate serde_json;
+extern crate tiny_http;
+
+use std::any::Any;
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::panic;
+use std::path::{Component, Path, PathBuf};
+
+use self::serde::Serialize;
+use self::serde_json::Value;
+use self::tiny_http::{Header, Method, Request, Response, Server};
+
+use types::*;
+use parser::*;
+use distribution::*;
+use migrate::*;
+use schedule::{get_timetable, read_schedule};
+use statistics::*;
+use verify::verify;
+
+type Reply = Response<Cursor<Vec<u8>>>;
+
+/// The web interface. It works on a single input file like the command line and writes the
+/// distribution to "Alban says.txt" and "Alban says.json" in the working directory.
+pub struct WebInterface {
+    path: String,            // the input
+    assets: Option<PathBuf>, // the pages are read from here instead of the built in ones
+    distribution: Option<(JsonData, Distribution)>, // together with the input it was made from
+}
+
+/// the pages of the interface, they are part of the executable
+const ASSETS: &[(&str, &[u8])] = &[
+    ("index.html", include_bytes!("gui/assets/index.html")),
+    ("app.js", include_bytes!("gui/assets/app.js")),
+    ("style.css", include_bytes!("gui/assets/style.css")),
+];
+
+/// the names this computer is reached by, with or without a port
+const LOCAL_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "[::1]"];
+
+fn is_local_host(host: &str) -> bool {
+    LOCAL_HOSTS.iter().any(|local| match host.strip_prefix(local) {
+        Some("") => true,
+        Some(port) => port.strip_prefix(':').is_some_and(|port| port.parse::<u16>().is_ok()),
+        None => false,
+    })
+}
+
+/// Only pages of this computer may use the interface. Other host names are refused so that
+/// pages of other sites can not reach it by rebinding their name to 127.0.0.1, and browsers
+/// give the origin of pages that send inputs or start distributions from another site.
+fn check_origin(request: &Request) -> Result<(), String> {
+    let header = |name: &'static str| {
+        request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv(name))
+            .map(|header| header.value.as_str().to_string())
+    };
+    match header("Host") {
+        Some(ref host) if is_local_host(host) => {}
+        Some(host) => return Err(format!("the interface only answers requests to localhost, not {}", host)),
+        None => return Err("the interface only answers requests to localhost".to_string()),
+    }
+    match header("Origin") {
+        Some(ref origin) if !origin.strip_prefix("http://").is_some_and(is_local_host) => {
+            Err(format!("the interface only answers pages of localhost, not {}", origin))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn get_content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        _ => "text/plain; charset=utf-8",
+    }
+}
+
+fn reply(status: u16, content_type: &str, body: Vec<u8>) -> Reply {
+    let response = Response::from_data(body).with_status_code(status);
+    match Header::from_bytes("Content-Type", content_type) {
+        Ok(header) => response.with_header(header),
+        Err(_) => response,
+    }
+}
+
+fn reply_json<T: Serialize>(status: u16, value: &T) -> Reply {
+    match serde_json::to_vec_pretty(value) {
+        Ok(body) => reply(status, "application/json", body),
+        Err(error) => reply_error(500, error.to_string()),
+    }
+}
+
+fn reply_error(status: u16, error: String) -> Reply {
+    let report = JsonReport {
+        fehler: vec![error],
+        probleme: Vec::new(),
+    };
+    match serde_json::to_vec_pretty(&report) {
+        Ok(body) => reply(status, "application/json", body),
+        Err(_) => reply(status, "text/plain; charset=utf-8", report.fehler[0].clone().into_bytes()),
+    }
+}
+
+/// a file as download with its name
+fn reply_file(path: &str) -> Reply {
+    let mut body = Vec::new();
+    if let Err(error) = File::open(path).and_then(|mut file| file.read_to_end(&mut body)) {
+        return reply_error(404, format!("Unable to read {}: {}", path, error));
+    }
+    let name = Path::new(path)
+        .file_name()
+        .map_or(path.to_string(), |name| name.to_string_lossy().to_string());
+    let response = reply(200, get_content_type(Path::new(path)), body);
+    match Header::from_bytes("Content-Disposition", format!("attachment; filename=\"{}\"", name)) {
+        Ok(header) => response.with_header(header),
+        Err(_) => response,
+    }
+}
+
+/// the message a panic was started with
+fn get_panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<String>() {
+        Some(message) => message.clone(),
+        None => match payload.downcast_ref::<&str>() {
+            Some(message) => message.to_string(),
+            None => "the request failed".to_string(),
+        },
+    }
+}
+
+/// the format of an uploaded input, given by "?format=toml" or "?format=yaml"
+fn get_format(query: &str) -> InputFormat {
+    let format = query
+        .split('&')
+        .find_map(|parameter| parameter.strip_prefix("format="))
+        .unwrap_or("json");
+    InputFormat::from_path(&format!("input.{}", format))
+}
+
+impl WebInterface {
+    pub fn new(path: &str, assets: Option<&Path>) -> WebInterface {
+        WebInterface {
+            path: path.to_string(),
+            assets: assets.map(Path::to_path_buf),
+            distribution: None,
+        }
+    }
+
+    /// Answers a single request. A request that fails answers with its reason and leaves
+    /// the interface running.
+    pub fn handle(&mut self, mut request: Request) {
+        let url = request.url().to_string();
+        let method = request.method().clone();
+        let mut body = String::new();
+        let response = match check_origin(&request) {
+            Err(error) => reply_error(403, error),
+            Ok(()) => match request.as_reader().read_to_string(&mut body) {
+                Err(error) => reply_error(400, format!("Unable to read the request: {}", error)),
+                Ok(_) => match panic::catch_unwind(panic::AssertUnwindSafe(|| self.route(&method, &url, &body))) {
+                    Ok(response) => response,
+                    Err(payload) => reply_error(500, get_panic_message(payload.as_ref())),
+                },
+            },
+        };
+        if let Err(error) = request.respond(response) {
+            println!("Unable to answer {}: {}", url, error);
+        }
+    }
+
+    fn route(&mut self, method: &Method, url: &str, body: &str) -> Reply {
+        let (path, query) = match url.find('?') {
+            Some(position) => (&url[..position], &url[position + 1..]),
+            None => (url, ""),
+        };
+        if let Some(name) = path.strip_prefix("/assets/") {
+            return self.get_asset(name);
+        }
+        if let Some(student) = path.strip_prefix("/api/students/") {
+            if *method == Method::Get {
+                return self.get_student_timetable(student);
+            }
+        }
+        match (method, path) {
+            (&Method::Get, "/") => self.get_asset("index.html"),
+            (&Method::Get, "/api/input") => self.get_input(),
+            (&Method::Put, "/api/input") => self.put_input(body, get_format(query)),
+            (&Method::Post, "/api/validate") => self.validate_input(body, get_format(query)),
+            (&Method::Post, "/api/distribute") => self.run_distribution(),
+            (&Method::Get, "/api/schedule") => self.get_schedule(),
+            (&Method::Post, "/api/verify") => self.verify_schedule(body),
+            (&Method::Get, "/api/statistics") => self.get_statistics_report(),
+            (&Method::Get, "/download/input") => reply_file(&self.path),
+            (&Method::Get, "/download/text") => reply_file("Alban says.txt"),
+            (&Method::Get, "/download/schedule") => reply_file("Alban says.json"),
+            _ => reply_error(404, format!("there is no {} {}", method, path)),
+        }
+    }
+
+    /// a file of the pages, paths out of the assets are refused
+    fn get_asset(&self, name: &str) -> Reply {
+        let assets = match self.assets {
+            Some(ref assets) => assets,
+            None => {
+                return match ASSETS.iter().find(|asset| asset.0 == name) {
+                    Some(&(_, body)) => reply(200, get_content_type(Path::new(name)), body.to_vec()),
+                    None => reply_error(404, format!("there is no asset {}", name)),
+                }
+            }
+        };
+        let name = Path::new(name);
+        if !name.components().all(|component| matches!(component, Component::Normal(_))) {
+            return reply_error(404, format!("there is no asset {}", name.display()));
+        }
+        let path = assets.join(name);
+        let mut body = Vec::new();
+        match File::open(&path).and_then(|mut file| file.read_to_end(&mut body)) {
+            Ok(_) => reply(200, get_content_type(&path), body),
+            Err(error) => reply_error(404, format!("Unable to read {}: {}", path.display(), error)),
+        }
+    }
+
+    /// the input as json, a new one if there is no input file yet
+    fn get_input(&self) -> Reply {
+        if Path::new(&self.path).exists() {
+            return reply_json(200, &read_input_value(&self.path));
+        }
+        match serde_json::from_str::<Value>(INPUT_TEMPLATE) {
+            Ok(template) => reply_json(200, &template),
+            Err(error) => reply_error(500, error.to_string()),
+        }
+    }
+
+    /// Reads an input that was sent in the body. The roster of a gruppenImport is expected
+    /// next to the input file.
+    fn check_input(&self, body: &str, format: InputFormat) -> Result<(Value, JsonData), Reply> {
+        let value = match parse_input_value(body, format) {
+            Ok(value) => value,
+            Err(error) => return Err(reply_error(400, error)),
+        };
+        let mut data = match parse_value(&value) {
+            Ok(data) => data,
+            Err(errors) => {
+                let report = JsonReport {
+                    fehler: errors,
+                    probleme: Vec::new(),
+                };
+                return Err(reply_json(422, &report));
+            }
+        };
+        // the groups are part of the input, a failed import rejects it like a wrong key
+        if let Err(payload) = panic::catch_unwind(panic::AssertUnwindSafe(|| import_groups(&mut data, &self.path))) {
+            let report = JsonReport {
+                fehler: vec![get_panic_message(payload.as_ref())],
+                probleme: Vec::new(),
+            };
+            return Err(reply_json(422, &report));
+        }
+        Ok((value, data))
+    }
+
+    /// reports the problems of an input without saving it
+    fn validate_input(&self, body: &str, format: InputFormat) -> Reply {
+        match self.check_input(body, format) {
+            Ok((_, data)) => {
+                let report = JsonReport {
+                    fehler: Vec::new(),
+                    probleme: get_problems(&data),
+                };
+                reply_json(200, &report)
+            }
+            Err(response) => response,
+        }
+    }
+
+    /// Checks an input and saves it as json if it can be read. Inputs in other formats are
+    /// saved next to them, like the terminal interface does.
+    fn put_input(&mut self, body: &str, format: InputFormat) -> Reply {
+        let (value, data) = match self.check_input(body, format) {
+            Ok(input) => input,
+            Err(response) => return response,
+        };
+        let path = match InputFormat::from_path(&self.path) {
+            InputFormat::Json => self.path.clone(),
+            _ => Path::new(&self.path).with_extension("json").to_string_lossy().to_string(),
+        };
+        back_up(&path);
+        write_input(&path, &value);
+        self.path = path;
+        self.distribution = None;
+        let report = JsonReport {
+            fehler: Vec::new(),
+            probleme: get_problems(&data),
+        };
+        reply_json(200, &report)
+    }
+
+    /// distributes the saved input and answers with the schedule
+    fn run_distribution(&mut self) -> Reply {
+        let input = parse_file(&self.path);
+        let options = Options {
+            quiet: true,
+            ..Options::default()
+        };
+        let distribution = distribute(&input, &options);
+        if let Err(error) = write_files(&input, &distribution) {
+            return reply_error(500, format!("Unable to write Alban says.txt: {}", error));
+        }
+        let response = reply_json(200, &distribution.schedule);
+        self.distribution = Some((input, distribution));
+        response
+    }
+
+    /// the last distribution, or the one of an earlier run if there is none yet
+    fn get_distribution(&mut self) -> Option<&(JsonData, Distribution)> {
+        if self.distribution.is_none() && Path::new("Alban says.json").exists() {
+            let input = parse_file(&self.path);
+            let distribution = restore_distribution(&input, read_schedule("Alban says.json"));
+            self.distribution = Some((input, distribution));
+        }
+        self.distribution.as_ref()
+    }
+
+    fn get_schedule(&mut self) -> Reply {
+        match self.get_distribution() {
+            Some((_, distribution)) => reply_json(200, &distribution.schedule),
+            None => reply_error(404, "there is no distribution yet".to_string()),
+        }
+    }
+
+    fn get_statistics_report(&mut self) -> Reply {
+        match self.get_distribution() {
+            Some((input, distribution)) => {
+                let statistics = get_statistics(
+                    input,
+                    &distribution.weeks,
+                    &distribution.students,
+                    &distribution.rotations,
+                );
+                let conflicts = get_conflicts(&distribution.rotations);
+                reply_json(200, &get_json_statistics(&statistics, &conflicts))
+            }
+            None => reply_error(404, "there is no distribution yet".to_string()),
+        }
+    }
+
+    fn get_student_timetable(&mut self, student: &str) -> Reply {
+        let (input, distribution) = match self.get_distribution() {
+            Some(distribution) => distribution,
+            None => return reply_error(404, "there is no distribution yet".to_string()),
+        };
+        match student.parse::<u64>() {
+            Ok(student) if student >= 1 && student <= input.studentenAnzahl => {
+                reply_json(200, &get_timetable(&distribution.schedule, student))
+            }
+            _ => reply_error(404, format!("there is no student {}", student)),
+        }
+    }
+
+    /// Checks a schedule against the saved input, the last distribution if the body is
+    /// empty. The violations are the errors of the report.
+    fn verify_schedule(&mut self, body: &str) -> Reply {
+        let violations = if body.trim().is_empty() {
+            match self.get_distribution() {
+                Some((input, distribution)) => verify(input, &distribution.schedule),
+                None => return reply_error(404, "there is no distribution yet".to_string()),
+            }
+        } else {
+            match serde_json::from_str::<JsonSchedule>(body) {
+                Ok(schedule) => verify(&parse_file(&self.path), &schedule),
+                Err(error) => return reply_error(400, format!("the body is no schedule: {}", error)),
+            }
+        };
+        let report = JsonReport {
+            fehler: violations,
+            probleme: Vec::new(),
+        };
+        reply_json(200, &report)
+    }
+}
+
+/// Serves the web interface on the address until the program is stopped (alban serve).
+pub fn serve(address: &str, path: &str, assets: Option<&Path>) {
+    let server = match Server::http(address) {
+        Ok(server) => server,
+        Err(error) => panic!("Unable to listen on {}: {}", address, error),
+    };
+    println!("the web interface is running on http://{}, stop it with Ctrl+C", server.server_addr());
+    let mut interface = WebInterface::new(path, assets);
+    for request in server.incoming_requests() {
+        interface.handle(request);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn knows_the_names_of_this_computer() {
+        for host in ["localhost", "localhost:8080", "127.0.0.1:80", "[::1]:8080"].iter() {
+            assert!(is_local_host(host), "{}", host);
+        }
+        for host in ["localhost.evil.example", "127.0.0.1.nip.io", "localhost:", "evil.example:8080"].iter() {
+            assert!(!is_local_host(host), "{}", host);
+        }
+    }
+
+    #[test]
+    fn serves_the_built_in_pages() {
+        let interface = WebInterface::new("input.json", None);
+        for &(name, _) in ASSETS.iter() {
+            assert_eq!(interface.get_asset(name).status_code().0, 200, "{}", name);
+        }
+        assert_eq!(interface.get_asset("../input.json").status_code().0, 404);
+    }
+}
+
@@ -0,0 +1,259 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::LinkedList;
+use std::io;
+use std::io::Write;
+use std::rc::Rc;
+
+use types::*;
+
+/// the entry of the student in "studenten", if there is one
+fn get_entry(parsed_data: &JsonData, number: u64) -> Option<&JsonStudent> {
+    parsed_data.studenten.iter().find(|entry| entry.nummer == number)
+}
+
+/// whether the student does not visit the courses of the type
+pub fn is_exempt(parsed_data: &JsonData, number: u64, course_type: CourseType) -> bool {
+    get_entry(parsed_data, number).is_some_and(|entry| entry.befreit.contains(&course_type))
+}
+
+/// the sessions of the course type the student completed in earlier semesters
+pub fn get_credit(parsed_data: &JsonData, number: u64, course_type: CourseType) -> u64 {
+    get_entry(parsed_data, number)
+        .and_then(|entry| entry.absolviert.get(course_type))
+        .unwrap_or(0)
+}
+
+/// The quota of the course type for the given student. Students that are not enrolled for
+/// the whole semester get a share of the quota that matches their weeks (rounded). Extra
+/// sessions of the student are added, exempt students have no quota.
+pub fn get_quota(parsed_data: &JsonData, student: &Student, course_type: CourseType) -> Option<u64> {
+    if is_exempt(parsed_data, student.number, course_type) {
+        return None;
+    }
+    let extra = get_entry(parsed_data, student.number).and_then(|entry| entry.zusaetzlich.get(course_type));
+    let quota = match (parsed_data.kontingente.get(course_type), extra) {
+        (None, None) => return None,
+        (quota, extra) => (quota.unwrap_or(0), extra.unwrap_or(0)),
+    };
+    let first_week = parsed_data.wochen.kwAnfang;
+    let last_week = parsed_data.wochen.kwEnde;
+    let semester_weeks = last_week + 1 - first_week;
+    let enrolled_weeks =
+        (student.last_week.min(last_week) + 1).saturating_sub(student.first_week.max(first_week));
+    Some((quota.0 * enrolled_weeks + semester_weeks / 2) / semester_weeks + quota.1)
+}
+
+/// the sessions the student still has to visit this semester, the quota without the
+/// sessions of earlier semesters
+pub fn get_required(parsed_data: &JsonData, student: &Student, course_type: CourseType) -> Option<u64> {
+    get_quota(parsed_data, student, course_type)
+        .map(|quota| quota.saturating_sub(get_credit(parsed_data, student.number, course_type)))
+}
+
+/// Counts the sessions of every student and compares them with their quotas.
+/// The sessions that were lost to holidays and made up are taken from the groups of
+/// the student.
+pub fn get_statistics(
+    parsed_data: &JsonData,
+    weeks: &[Week],
+    students: &LinkedList<Rc<Student>>,
+    rotations: &Rotations,
+) -> Vec<StudentStatistics> {
+    let mut statistics: Vec<StudentStatistics> = students
+        .iter()
+        .map(|student| StudentStatistics {
+            student: student.clone(),
+            sessions: [0; 4],
+            quotas: {
+                let mut quotas = [None; 4];
+                for (index, course_type) in COURSE_TYPES.iter().enumerate() {
+                    quotas[index] = get_quota(parsed_data, student, *course_type);
+                }
+                quotas
+            },
+            credited: {
+                let mut credited = [0; 4];
+                for (index, course_type) in COURSE_TYPES.iter().enumerate() {
+                    credited[index] = get_credit(parsed_data, student.number, *course_type);
+                }
+                credited
+            },
+            exempt: {
+                let mut exempt = [false; 4];
+                for (index, course_type) in COURSE_TYPES.iter().enumerate() {
+                    exempt[index] = is_exempt(parsed_data, student.number, *course_type);
+                }
+                exempt
+            },
+            lost: 0,
+            made_up: 0,
+        })
+        .collect();
+    for (_, groups) in rotations.named() {
+        for group in groups.iter() {
+            for student in group.participants.borrow().iter() {
+                if let Some(entry) = statistics
+                    .iter_mut()
+                    .find(|entry| entry.student == *student)
+                {
+                    entry.lost += group.lost.get();
+                    entry.made_up += group.made_up.get();
+                }
+            }
+        }
+    }
+    for day in weeks.iter().flat_map(|week| week.days.iter()) {
+        for course in day.courses.borrow().iter() {
+            let index = COURSE_TYPES
+                .iter()
+                .position(|course_type| *course_type == course.course_type)
+                .unwrap_or(0);
+            for student in course.participants.borrow().iter() {
+                if let Some(entry) = statistics
+                    .iter_mut()
+                    .find(|entry| entry.student == *student)
+                {
+                    entry.sessions[index] += 1;
+                }
+            }
+        }
+    }
+    statistics
+}
+
+/// the statistics in the form of the json exports
+pub fn get_json_statistics(statistics: &[StudentStatistics], conflicts: &[u64; 4]) -> JsonStatistics {
+    JsonStatistics {
+        studenten: statistics
+            .iter()
+            .map(|entry| JsonStudentStatistics {
+                nummer: entry.student.number,
+                besuche: JsonCourseCounts::from(entry.sessions),
+                soll: JsonQuotas::from(entry.quotas),
+                angerechnet: JsonCourseCounts::from(entry.credited),
+                befreit: COURSE_TYPES
+                    .iter()
+                    .zip(entry.exempt.iter())
+                    .filter(|&(_, exempt)| *exempt)
+                    .map(|(course_type, _)| *course_type)
+                    .collect(),
+                ausgefallen: entry.lost,
+                nachgeholt: entry.made_up,
+            })
+            .collect(),
+        konflikte: JsonCourseCounts::from(*conflicts),
+    }
+}
+
+/// whether the student visited fewer sessions of a course type than the quota demands,
+/// the sessions of earlier semesters included
+pub fn misses_quota(statistic: &StudentStatistics) -> bool {
+    (0..COURSE_TYPES.len()).any(|index| {
+        statistic.quotas[index].is_some_and(|quota| statistic.sessions[index] + statistic.credited[index] < quota)
+    })
+}
+
+/// How often the groups of every course type (in the order of COURSE_TYPES) missed their
+/// turn because their students were in a course of another type at the same time.
+pub fn get_conflicts(rotations: &Rotations) -> [u64; 4] {
+    let mut conflicts = [0; 4];
+    for (_, groups) in rotations.named() {
+        for group in groups.iter() {
+            if let Some(index) = COURSE_TYPES
+                .iter()
+                .position(|course_type| *course_type == group.group_type)
+            {
+                conflicts[index] += group.conflicts.get();
+            }
+        }
+    }
+    conflicts
+}
+
+/// writes the lost conflicts of every course type
+pub fn print_conflicts<T: Write>(file: &mut T, conflicts: &[u64; 4]) -> Result<(), io::Error> {
+    writeln!(file, "Konflikte (Gruppen, die wegen eines anderen Kurses übersprungen wurden)")?;
+    for (course_type, count) in COURSE_TYPES.iter().zip(conflicts.iter()) {
+        writeln!(file, "{} {}", course_type, count)?;
+    }
+    Ok(())
+}
+
+/// writes the statistics of all students as a table
+pub fn print_statistics<T: Write>(
+    file: &mut T,
+    statistics: &[StudentStatistics],
+) -> Result<(), io::Error> {
+    // the columns for holidays are only needed if sessions were lost
+    let has_lost = statistics.iter().any(|entry| entry.lost > 0);
+    writeln!(file, "Statistik (Sitzungen/Kontingent)")?;
+    write!(file, "Student")?;
+    for course_type in COURSE_TYPES.iter() {
+        write!(file, " {}", course_type)?;
+    }
+    if has_lost {
+        write!(file, " Ausfall    Nachgeholt")?;
+    }
+    writeln!(file)?;
+    for entry in statistics.iter() {
+        let mut row = entry.to_string();
+        if has_lost {
+            row.push_str(&format!(" {:<10} {}", entry.lost, entry.made_up));
+        }
+        writeln!(file, "{}", row.trim_end())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use super::*;
+    use distribution::Options;
+    use fixtures::{distribute_quietly, get_input};
+
+    #[test]
+    fn honours_quota_overrides() {
+        let data = get_input(serde_json::json!({
+            "kontingente": {"Zahnerhalt": 20},
+            "studenten": [
+                {"nummer": 3, "absolviert": {"Zahnerhalt": 15}},
+                {"nummer": 7, "befreit": ["Exkurs"]}
+            ]
+        }));
+        let distribution = distribute_quietly(&data, Options::default());
+        let statistics = get_statistics(
+            &data,
+            &distribution.weeks,
+            &distribution.students,
+            &distribution.rotations,
+        );
+        let statistics = get_json_statistics(&statistics, &get_conflicts(&distribution.rotations));
+
+        let third = &statistics.studenten[2];
+        assert_eq!(third.angerechnet.Zahnerhalt, 15);
+        // the others got the seats the third student did not need
+        let others = statistics.studenten.iter().filter(|student| student.nummer != 3);
+        assert!(others.map(|student| student.besuche.Zahnerhalt).min().unwrap() > third.besuche.Zahnerhalt);
+        let seventh = &statistics.studenten[6];
+        assert_eq!(seventh.befreit, vec![CourseType::Exkurs]);
+        assert_eq!(seventh.besuche.Exkurs, 0);
+        assert_eq!(seventh.soll.Exkurs, None);
+    }
+}
@@ -0,0 +1,755 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+extern crate serde_json;
+
+use std::io;
+use std::io::{BufRead, Write};
+use std::panic;
+use std::path::Path;
+
+use self::serde_json::{Map, Value};
+
+use types::*;
+use parser::*;
+use distribution::*;
+use migrate::*;
+use statistics::*;
+
+// ANSI escape codes, the terminals of Windows 10 understand them as well
+const HIGHLIGHT: &str = "\x1b[7m";
+const RESET: &str = "\x1b[0m";
+
+/// The terminal interface. It edits a copy of the input and only writes it when it is saved,
+/// the keys it does not edit are written back as they were read.
+pub struct Tui<R, W> {
+    reader: R,
+    writer: W,
+    path: String,
+    original: Value,
+    data: JsonData,
+    changed: bool,
+    groups_changed: bool, // the groups of a gruppenImport were changed by hand
+    distribution: Option<Distribution>,
+}
+
+/// the numbers in a line like "1 7 24" or "1, 7, 24"
+fn parse_numbers(line: &str) -> Option<Vec<u64>> {
+    line.split(|character: char| character == ',' || character.is_whitespace())
+        .filter(|number| !number.is_empty())
+        .map(|number| number.parse::<u64>().ok())
+        .collect()
+}
+
+/// the index of a list entry given by its number, the entries start with 1
+fn parse_entry(number: &str, count: usize) -> Option<usize> {
+    number
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|number| *number >= 1 && *number <= count)
+        .map(|number| number - 1)
+}
+
+/// marks every occurrence of the student number in the line
+fn highlight(line: &str, student: u64) -> String {
+    let number = student.to_string();
+    let mut result = String::new();
+    let mut rest = line;
+    while let Some(position) = rest.find(&number) {
+        let before = rest[..position].chars().last();
+        let after = rest[position + number.len()..].chars().next();
+        let is_number = !before.is_some_and(|character| character.is_ascii_digit())
+            && !after.is_some_and(|character| character.is_ascii_digit());
+        result.push_str(&rest[..position]);
+        if is_number {
+            result.push_str(HIGHLIGHT);
+            result.push_str(&number);
+            result.push_str(RESET);
+        } else {
+            result.push_str(&number);
+        }
+        rest = &rest[position + number.len()..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// removes the keys without a value, so that written entries only contain what was entered
+fn compact(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .filter(|(_, value)| !value.is_null() && value.as_array().is_none_or(|array| !array.is_empty()))
+                .collect(),
+        ),
+        Value::Array(array) => Value::Array(array.into_iter().map(compact).collect()),
+        value => value,
+    }
+}
+
+impl<R: BufRead, W: Write> Tui<R, W> {
+    /// Opens the input of the path, or a new one if the file does not exist.
+    pub fn new(reader: R, writer: W, path: &str) -> Tui<R, W> {
+        let (original, data) = if Path::new(path).exists() {
+            (read_input_value(path), parse_file(path))
+        } else {
+            let original: Value = serde_json::from_str(INPUT_TEMPLATE).unwrap_or_default();
+            let data = match serde_json::from_value(original.clone()) {
+                Ok(data) => data,
+                Err(error) => panic!("the template is invalid: {}", error),
+            };
+            (original, data)
+        };
+        Tui {
+            reader,
+            writer,
+            path: path.to_string(),
+            original,
+            data,
+            changed: false,
+            groups_changed: false,
+            distribution: None,
+        }
+    }
+
+    /// asks a question and returns the answer without surrounding spaces, none at the end of the input
+    fn ask(&mut self, question: &str) -> io::Result<Option<String>> {
+        write!(self.writer, "{} ", question)?;
+        self.writer.flush()?;
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(line.trim().to_string()))
+    }
+
+    /// asks for a number, an empty answer keeps the current one
+    fn ask_number(&mut self, question: &str, current: Option<u64>) -> io::Result<Option<u64>> {
+        loop {
+            let shown = current.map_or("-".to_string(), |current| current.to_string());
+            let answer = match self.ask(&format!("{} [{}]:", question, shown))? {
+                Some(answer) => answer,
+                None => return Ok(current),
+            };
+            if answer.is_empty() {
+                return Ok(current);
+            }
+            if answer == "-" {
+                return Ok(None);
+            }
+            match answer.parse::<u64>() {
+                Ok(number) => return Ok(Some(number)),
+                Err(_) => writeln!(self.writer, "{} is no number", answer)?,
+            }
+        }
+    }
+
+    /// asks for a list of numbers, an empty answer keeps the current ones
+    fn ask_numbers(&mut self, question: &str, current: &[u64]) -> io::Result<Vec<u64>> {
+        loop {
+            let answer = match self.ask(&format!("{} {:?}:", question, current))? {
+                Some(answer) => answer,
+                None => return Ok(current.to_vec()),
+            };
+            if answer.is_empty() {
+                return Ok(current.to_vec());
+            }
+            match parse_numbers(&answer) {
+                Some(numbers) => return Ok(numbers),
+                None => writeln!(self.writer, "expected numbers separated by spaces")?,
+            }
+        }
+    }
+
+    /// the main menu
+    pub fn run(&mut self) -> io::Result<()> {
+        loop {
+            writeln!(self.writer)?;
+            writeln!(
+                self.writer,
+                "alban - {}{}",
+                self.path,
+                if self.changed { " (changed)" } else { "" }
+            )?;
+            writeln!(
+                self.writer,
+                " 1 weeks              KW {} to {}",
+                self.data.wochen.kwAnfang, self.data.wochen.kwEnde
+            )?;
+            writeln!(
+                self.writer,
+                " 2 students           {}, {} not enrolled for the whole semester",
+                self.data.studentenAnzahl,
+                self.data.studenten.len()
+            )?;
+            writeln!(self.writer, " 3 Curriculum groups  {}", self.data.curriculumGruppen.len())?;
+            writeln!(self.writer, " 4 Exkurs groups      {}", self.data.exkursGruppen.len())?;
+            writeln!(self.writer, " 5 holidays           {}", self.data.feiertage.len())?;
+            writeln!(self.writer, " 6 distribute")?;
+            writeln!(
+                self.writer,
+                " 7 browse             {}",
+                if self.distribution.is_some() { "the distribution week by week" } else { "(distribute first)" }
+            )?;
+            writeln!(self.writer, " 8 save")?;
+            writeln!(self.writer, " q quit")?;
+            let answer = match self.ask(">")? {
+                Some(answer) => answer,
+                None => return Ok(()),
+            };
+            match answer.as_str() {
+                "1" => self.edit_weeks()?,
+                "2" => self.edit_students()?,
+                "3" => self.edit_groups(CourseType::Curriculum)?,
+                "4" => self.edit_groups(CourseType::Exkurs)?,
+                "5" => self.edit_holidays()?,
+                "6" => self.distribute()?,
+                "7" => self.browse()?,
+                "8" => self.save()?,
+                "q" => {
+                    if !self.changed {
+                        return Ok(());
+                    }
+                    if let Some(answer) = self.ask("discard the unsaved changes? (y/n)")? {
+                        if answer == "y" {
+                            return Ok(());
+                        }
+                    }
+                }
+                _ => writeln!(self.writer, "unknown choice {}", answer)?,
+            }
+        }
+    }
+
+    fn edit_weeks(&mut self) -> io::Result<()> {
+        let first = self.ask_number("first week", Some(self.data.wochen.kwAnfang))?;
+        let last = self.ask_number("last week", Some(self.data.wochen.kwEnde))?;
+        match (first, last) {
+            (Some(first), Some(last)) if last >= first => {
+                self.data.wochen.kwAnfang = first;
+                self.data.wochen.kwEnde = last;
+                self.changed = true;
+            }
+            _ => writeln!(self.writer, "the last week has to follow the first, nothing was changed")?,
+        }
+        Ok(())
+    }
+
+    fn edit_students(&mut self) -> io::Result<()> {
+        if let Some(count) = self.ask_number("number of students", Some(self.data.studentenAnzahl))? {
+            if count != self.data.studentenAnzahl {
+                self.data.studentenAnzahl = count;
+                self.changed = true;
+            }
+        }
+        loop {
+            writeln!(self.writer)?;
+            writeln!(self.writer, "students that are not enrolled for the whole semester")?;
+            for (index, student) in self.data.studenten.iter().enumerate() {
+                writeln!(
+                    self.writer,
+                    "{:>3}. student {:>3} from KW {} to KW {}",
+                    index + 1,
+                    student.nummer,
+                    student.von.unwrap_or(self.data.wochen.kwAnfang),
+                    student.bis.unwrap_or(self.data.wochen.kwEnde)
+                )?;
+            }
+            let answer = match self.ask("a add or change, r<entry> remove, enter back:")? {
+                Some(answer) => answer,
+                None => return Ok(()),
+            };
+            if answer.is_empty() {
+                return Ok(());
+            } else if answer == "a" {
+                let number = match self.ask_number("student", None)? {
+                    Some(number) => number,
+                    None => continue,
+                };
+                let current = self.data.studenten.iter().position(|student| student.nummer == number);
+                let (from, until) = match current {
+                    Some(index) => (self.data.studenten[index].von, self.data.studenten[index].bis),
+                    None => (None, None),
+                };
+                let from = self.ask_number("first week (- for the beginning of the semester)", from)?;
+                let until = self.ask_number("last week (- for the end of the semester)", until)?;
+                // the quota overrides of the student stay as they are
+                match current {
+                    Some(index) => {
+                        self.data.studenten[index].von = from;
+                        self.data.studenten[index].bis = until;
+                    }
+                    None => self.data.studenten.push(JsonStudent {
+                        nummer: number,
+                        von: from,
+                        bis: until,
+                        zusaetzlich: JsonQuotas::default(),
+                        absolviert: JsonQuotas::default(),
+                        befreit: Vec::new(),
+                    }),
+                }
+                self.changed = true;
+            } else if let Some(entry) = answer.strip_prefix('r') {
+                match parse_entry(entry, self.data.studenten.len()) {
+                    Some(index) => {
+                        self.data.studenten.remove(index);
+                        self.changed = true;
+                    }
+                    None => writeln!(self.writer, "there is no entry {}", entry)?,
+                }
+            } else {
+                writeln!(self.writer, "unknown choice {}", answer)?;
+            }
+        }
+    }
+
+    fn edit_groups(&mut self, course_type: CourseType) -> io::Result<()> {
+        loop {
+            writeln!(self.writer)?;
+            if self.data.gruppenImport.is_some() && !self.groups_changed {
+                writeln!(self.writer, "the groups are imported, changing them replaces the import when saved")?;
+            }
+            let groups = match course_type {
+                CourseType::Exkurs => &self.data.exkursGruppen,
+                _ => &self.data.curriculumGruppen,
+            };
+            for (index, group) in groups.iter().enumerate() {
+                writeln!(self.writer, "{:>3}. {:?}", index + 1, group)?;
+            }
+            let count = groups.len();
+            let answer = match self.ask(&format!(
+                "{:?} groups: a add, c<group> change, r<group> remove, enter back:",
+                course_type
+            ))? {
+                Some(answer) => answer,
+                None => return Ok(()),
+            };
+            if answer.is_empty() {
+                return Ok(());
+            }
+            let mut groups = match course_type {
+                CourseType::Exkurs => self.data.exkursGruppen.clone(),
+                _ => self.data.curriculumGruppen.clone(),
+            };
+            if answer == "a" {
+                let group = self.ask_numbers("students of the new group", &[])?;
+                if !group.is_empty() {
+                    groups.push(group);
+                }
+            } else if answer.starts_with('c') || answer.starts_with('r') {
+                let index = match parse_entry(&answer[1..], count) {
+                    Some(index) => index,
+                    None => {
+                        writeln!(self.writer, "there is no group {}", &answer[1..])?;
+                        continue;
+                    }
+                };
+                if answer.starts_with('r') {
+                    groups.remove(index);
+                } else {
+                    let current = groups[index].clone();
+                    groups[index] = self.ask_numbers("students of the group", &current)?;
+                }
+            } else {
+                writeln!(self.writer, "unknown choice {}", answer)?;
+                continue;
+            }
+            match course_type {
+                CourseType::Exkurs => self.data.exkursGruppen = groups,
+                _ => self.data.curriculumGruppen = groups,
+            }
+            self.changed = true;
+            self.groups_changed = true;
+        }
+    }
+
+    fn edit_holidays(&mut self) -> io::Result<()> {
+        loop {
+            writeln!(self.writer)?;
+            for (index, holiday) in self.data.feiertage.iter().enumerate() {
+                let mut description = format!(
+                    "{:>3}. KW {} days {:?}",
+                    index + 1,
+                    holiday.woche,
+                    holiday.tage
+                );
+                if !holiday.kurse.is_empty() {
+                    description.push_str(&format!(" only {:?}", holiday.kurse));
+                }
+                if let Some(from) = holiday.ab {
+                    description.push_str(&format!(" from {}", from));
+                }
+                if let Some(until) = holiday.bis {
+                    description.push_str(&format!(" until {}", until));
+                }
+                writeln!(self.writer, "{}", description)?;
+            }
+            let answer = match self.ask("a add, r<entry> remove, enter back:")? {
+                Some(answer) => answer,
+                None => return Ok(()),
+            };
+            if answer.is_empty() {
+                return Ok(());
+            } else if answer == "a" {
+                let week = match self.ask_number("week", None)? {
+                    Some(week) => week,
+                    None => continue,
+                };
+                let days = self.ask_numbers("days (1-5)", &[])?;
+                if days.is_empty() || days.iter().any(|day| *day < 1 || *day > 5) {
+                    writeln!(self.writer, "the days have to be between 1 and 5, nothing was added")?;
+                    continue;
+                }
+                let courses = self
+                    .ask("only these course types (names separated by spaces, enter for all):")?
+                    .unwrap_or_default();
+                let mut course_types = Vec::new();
+                for name in courses.split_whitespace() {
+                    match COURSE_TYPES.iter().find(|course_type| format!("{:?}", course_type).eq_ignore_ascii_case(name)) {
+                        Some(course_type) => course_types.push(*course_type),
+                        None => writeln!(self.writer, "{} is no course type and is left out", name)?,
+                    }
+                }
+                let from = self.ask_number("only courses beginning at or after (- for all)", None)?;
+                let until = self.ask_number("only courses beginning before (- for all)", None)?;
+                self.data.feiertage.push(JsonHoliday {
+                    woche: week,
+                    tage: days,
+                    kurse: course_types,
+                    ab: from.map(|hour| hour as u8),
+                    bis: until.map(|hour| hour as u8),
+                });
+                self.data.feiertage.sort_by_key(|holiday| holiday.woche);
+                self.changed = true;
+            } else if let Some(entry) = answer.strip_prefix('r') {
+                match parse_entry(entry, self.data.feiertage.len()) {
+                    Some(index) => {
+                        self.data.feiertage.remove(index);
+                        self.changed = true;
+                    }
+                    None => writeln!(self.writer, "there is no entry {}", entry)?,
+                }
+            } else {
+                writeln!(self.writer, "unknown choice {}", answer)?;
+            }
+        }
+    }
+
+    /// lists the problems and asks whether to go on anyway
+    fn confirm_problems(&mut self) -> io::Result<bool> {
+        let problems = get_problems(&self.data);
+        if problems.is_empty() {
+            return Ok(true);
+        }
+        for problem in problems.iter() {
+            writeln!(self.writer, "{}", problem)?;
+        }
+        Ok(self.ask("continue anyway? (y/n)")?.is_some_and(|answer| answer == "y"))
+    }
+
+    /// distributes the edited input and writes "Alban says.txt" and "Alban says.json"
+    fn distribute(&mut self) -> io::Result<()> {
+        if !self.confirm_problems()? {
+            return Ok(());
+        }
+        let options = Options {
+            quiet: true,
+            ..Options::default()
+        };
+        let data = &self.data;
+        // a failed distribution reports its reason and leaves the interface running
+        let distribution = match panic::catch_unwind(panic::AssertUnwindSafe(|| distribute(data, &options))) {
+            Ok(distribution) => distribution,
+            Err(_) => {
+                writeln!(self.writer, "the distribution failed, see the message above")?;
+                return Ok(());
+            }
+        };
+        let statistics = match write_files(&self.data, &distribution) {
+            Ok(statistics) => statistics,
+            Err(error) => {
+                writeln!(self.writer, "Unable to write Alban says.txt: {}", error)?;
+                return Ok(());
+            }
+        };
+        let missing = statistics.iter().filter(|statistic| misses_quota(statistic)).count();
+        writeln!(
+            self.writer,
+            "{} weeks distributed and written to Alban says.txt and Alban says.json, {} students miss their quotas",
+            distribution.weeks.len(),
+            missing
+        )?;
+        self.distribution = Some(distribution);
+        Ok(())
+    }
+
+    /// shows the distribution week by week, the chosen student is highlighted
+    fn browse(&mut self) -> io::Result<()> {
+        let distribution = match self.distribution.take() {
+            Some(distribution) => distribution,
+            None => {
+                writeln!(self.writer, "there is no distribution yet")?;
+                return Ok(());
+            }
+        };
+        let result = self.browse_distribution(&distribution);
+        self.distribution = Some(distribution);
+        result
+    }
+
+    fn browse_distribution(&mut self, distribution: &Distribution) -> io::Result<()> {
+        let width = get_column_width(&distribution.weeks);
+        let mut index = 0;
+        let mut student = None;
+        loop {
+            let week = match distribution.weeks.get(index) {
+                Some(week) => week,
+                None => return Ok(()),
+            };
+            let mut text = Vec::new();
+            print_week(&mut text, week, width)?;
+            writeln!(self.writer)?;
+            for (number, line) in String::from_utf8_lossy(&text).lines().enumerate() {
+                // the first line is the week number, the second the names of the days
+                match student {
+                    Some(student) if number > 1 => writeln!(self.writer, "{}", highlight(line, student))?,
+                    _ => writeln!(self.writer, "{}", line)?,
+                }
+            }
+            if let Some(student) = student {
+                let courses: Vec<String> = distribution
+                    .schedule
+                    .wochen
+                    .iter()
+                    .filter(|scheduled| scheduled.nummer == week.number)
+                    .flat_map(|scheduled| scheduled.kurse.iter())
+                    .filter(|course| course.teilnehmer.contains(&student))
+                    .map(|course| {
+                        format!(
+                            "{} {:?} {}",
+                            (course.tag as usize)
+                                .checked_sub(1)
+                                .and_then(|index| DAY_NAMES.get(index))
+                                .unwrap_or(&""),
+                            course.kurs,
+                            course.beginn
+                        )
+                    })
+                    .collect();
+                writeln!(self.writer, "student {}: {}", student, courses.join(", "))?;
+            }
+            let answer = match self.ask("enter next, p previous, w<week> go to week, s<student> highlight, q back:")? {
+                Some(answer) => answer,
+                None => return Ok(()),
+            };
+            if answer.is_empty() || answer == "n" {
+                if index + 1 < distribution.weeks.len() {
+                    index += 1;
+                }
+            } else if answer == "p" {
+                index = index.saturating_sub(1);
+            } else if answer == "q" {
+                return Ok(());
+            } else if let Some(number) = answer.strip_prefix('w') {
+                let week = number.trim().parse::<u64>().ok();
+                match distribution.weeks.iter().position(|scheduled| Some(scheduled.number) == week) {
+                    Some(position) => index = position,
+                    None => writeln!(self.writer, "KW {} is not part of the semester", number.trim())?,
+                }
+            } else if let Some(number) = answer.strip_prefix('s') {
+                // s without a number ends the highlighting
+                student = number.trim().parse::<u64>().ok();
+            } else {
+                writeln!(self.writer, "unknown choice {}", answer)?;
+            }
+        }
+    }
+
+    /// the original input with the edited keys
+    fn get_output(&self) -> Value {
+        let original = match self.original {
+            Value::Object(ref object) => object.clone(),
+            _ => Map::new(),
+        };
+        let mut object = Map::new();
+        if !original.contains_key("version") {
+            object.insert("version".to_string(), Value::from(INPUT_VERSION));
+        }
+        object.extend(original);
+        let value = |value: Result<Value, serde_json::Error>| compact(value.unwrap_or_default());
+        set_input_key(&mut object, "studentenAnzahl", "studentCount", Value::from(self.data.studentenAnzahl));
+        set_input_key(&mut object, "wochen", "weeks", value(serde_json::to_value(&self.data.wochen)));
+        set_input_key(&mut object, "feiertage", "holidays", value(serde_json::to_value(&self.data.feiertage)));
+        if !self.data.studenten.is_empty() || object.contains_key("studenten") || object.contains_key("students") {
+            set_input_key(&mut object, "studenten", "students", value(serde_json::to_value(&self.data.studenten)));
+        }
+        if self.data.gruppenImport.is_none() || self.groups_changed {
+            set_input_key(
+                &mut object,
+                "curriculumGruppen",
+                "curriculumGroups",
+                Value::from(self.data.curriculumGruppen.clone()),
+            );
+            set_input_key(&mut object, "exkursGruppen", "exkursGroups", Value::from(self.data.exkursGruppen.clone()));
+            object.remove("gruppenImport");
+            object.remove("rosterImport");
+        }
+        Value::Object(object)
+    }
+
+    /// Writes the input as json. Inputs in other formats are written next to them, their
+    /// comments would be lost.
+    fn save(&mut self) -> io::Result<()> {
+        if !self.confirm_problems()? {
+            return Ok(());
+        }
+        let path = match InputFormat::from_path(&self.path) {
+            InputFormat::Json => self.path.clone(),
+            _ => Path::new(&self.path).with_extension("json").to_string_lossy().to_string(),
+        };
+        let output = self.get_output();
+        if let Err(error) = serde_json::from_value::<JsonData>(output.clone()) {
+            writeln!(self.writer, "the input would be invalid, nothing was saved: {}", error)?;
+            return Ok(());
+        }
+        back_up(&path);
+        write_input(&path, &output);
+        writeln!(self.writer, "saved to {}", path)?;
+        self.original = output;
+        self.path = path;
+        self.changed = false;
+        Ok(())
+    }
+}
+
+/// edits the input of the path in the terminal (alban tui)
+pub fn run_tui(path: &str) {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut tui = Tui::new(stdin.lock(), stdout.lock(), path);
+    if let Err(error) = tui.run() {
+        panic!("the terminal interface failed: {}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::*;
+    use fixtures::{distribute_quietly, get_input, get_input_value};
+
+    /// a directory with the sample input and the given changes as input.json
+    fn create_input(name: &str, changes: Value) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("alban-tui-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        let mut input = get_input_value();
+        if let (Some(input), Some(changes)) = (input.as_object_mut(), changes.as_object()) {
+            for (key, value) in changes.iter() {
+                input.insert(key.clone(), value.clone());
+            }
+        }
+        fs::write(directory.join("input.json"), input.to_string()).unwrap();
+        directory
+    }
+
+    /// runs the interface with the lines of the script as answers
+    fn run_script<'a>(path: &Path, script: &'a str) -> (Tui<&'a [u8], Vec<u8>>, String) {
+        let mut tui = Tui::new(script.as_bytes(), Vec::new(), &path.to_string_lossy());
+        tui.run().unwrap();
+        let output = String::from_utf8_lossy(&tui.writer).to_string();
+        (tui, output)
+    }
+
+    #[test]
+    fn edits_the_weeks() {
+        let directory = create_input("weeks", serde_json::json!({}));
+        let (tui, output) = run_script(&directory.join("input.json"), "1\n14\n20\n1\n20\n14\nq\ny\n");
+        assert_eq!((tui.data.wochen.kwAnfang, tui.data.wochen.kwEnde), (14, 20));
+        assert!(output.contains("KW 14 to 20"));
+        assert!(output.contains("the last week has to follow the first, nothing was changed"));
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn removes_a_student() {
+        let directory = create_input(
+            "students",
+            serde_json::json!({"studenten": [{"nummer": 3, "von": 14}, {"nummer": 5, "bis": 20}]}),
+        );
+        let (tui, output) = run_script(&directory.join("input.json"), "2\n\nr1\nr3\n\nq\ny\n");
+        let students: Vec<u64> = tui.data.studenten.iter().map(|student| student.nummer).collect();
+        assert_eq!(students, vec![5]);
+        assert_eq!(tui.data.studentenAnzahl, 26);
+        assert!(output.contains("  1. student   5 from KW 12 to KW 20"));
+        assert!(output.contains("there is no entry 3"));
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn saves_the_keys_it_did_not_edit() {
+        let directory = create_input(
+            "save",
+            serde_json::json!({
+                "kontingente": {"Zahnerhalt": 20},
+                "fixierungen": [{"woche": 15, "tag": 2, "kurs": "Exkurs", "beginn": 7, "gruppe": 4}],
+                "holidays": [{"week": 14, "days": [1], "courses": ["Exkurs"]}]
+            }),
+        );
+        let path = directory.join("input.json");
+        let mut original: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        original.as_object_mut().unwrap().remove("feiertage");
+        fs::write(&path, original.to_string()).unwrap();
+        let (_, output) = run_script(&path, "1\n\n21\n8\nq\n");
+        assert!(output.contains("saved to"));
+        let saved: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(saved["wochen"]["kwEnde"], 21);
+        for key in ["kontingente", "fixierungen", "exkursGruppen", "curriculumGruppen"].iter() {
+            assert_eq!(saved[key], original[key], "{}", key);
+        }
+        // the english key is kept instead of adding the german one
+        assert_eq!(saved["holidays"], serde_json::json!([{"woche": 14, "tage": [1], "kurse": ["Exkurs"]}]));
+        assert!(saved.get("feiertage").is_none());
+        assert!(directory.join("input.json.bak").exists());
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn browses_courses_without_a_valid_day() {
+        let directory = create_input("browse", serde_json::json!({}));
+        let mut distribution = distribute_quietly(&get_input(serde_json::json!({})), Options::default());
+        // hand-written schedules can contain any day
+        distribution.schedule.wochen[0].kurse.push(JsonScheduleCourse {
+            tag: 0,
+            kurs: CourseType::Exkurs,
+            beginn: 7,
+            teilnehmer: vec![1],
+            behandler: Vec::new(),
+        });
+        let script = "7\ns1\nq\nq\n";
+        let mut tui = Tui::new(script.as_bytes(), Vec::new(), &directory.join("input.json").to_string_lossy());
+        tui.distribution = Some(distribution);
+        tui.run().unwrap();
+        assert!(String::from_utf8_lossy(&tui.writer).contains("student 1: "));
+        let _ = fs::remove_dir_all(&directory);
+    }
+}
@@ -16,9 +16,9 @@
 
 use std::collections::LinkedList;
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CourseType {
     Curriculum,
     Exkurs,
@@ -26,7 +26,18 @@ pub enum CourseType {
     Zahnersatz,
 }
 
-///
+pub const DAY_NAMES: [&str; 5] = ["Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag"];
+
+pub const COURSE_TYPES: [CourseType; 4] = [
+    CourseType::Curriculum,
+    CourseType::Exkurs,
+    CourseType::Zahnerhalt,
+    CourseType::Zahnersatz,
+];
+
+/// the version of the input format, older inputs can be upgraded with alban migrate
+pub const INPUT_VERSION: u64 = 2;
+
 #[derive(Debug)]
 pub struct Week {
     pub number: u64,
@@ -38,53 +49,631 @@ pub struct Day {
     pub courses: RefCell<LinkedList<Course>>,
 }
 
+/// Regular courses are open to every group, make-up courses only to groups that lost
+/// sessions to holidays. Cancelled courses are never held, they only record who lost them.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CourseKind {
+    #[serde(rename = "regulaer")]
+    Regular,
+    #[serde(rename = "nachholen")]
+    MakeUp,
+    #[serde(rename = "ausgefallen")]
+    Cancelled,
+}
+
 #[derive(Debug)]
 pub struct Course {
     pub beginning: u8,
     pub course_type: CourseType,
+    pub kind: CourseKind,
     pub participants: RefCell<LinkedList<Rc<Student>>>,
+    pub operators: RefCell<LinkedList<Rc<Student>>>, // treating partners of Zahnersatz pairs
+    pub pinned: RefCell<LinkedList<Rc<Student>>>,    // have to get a seat in this course
+    pub forbidden: RefCell<LinkedList<Rc<Student>>>, // must not get a seat in this course
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Student {
     pub number: u64,
+    pub first_week: u64, // the student is enrolled from this week on
+    pub last_week: u64,  // until this week
 }
 
-pub struct StudentPrinter<'a>(pub &'a LinkedList<Rc<Student>>); // needed to print generic
+pub struct StudentPrinter<'a>(pub &'a LinkedList<Rc<Student>>, pub usize); // needed to print generic
 
-#[derive(Debug)]
+pub struct CoursePrinter<'a>(pub &'a Course, pub usize); // marks the operators of a course
 
+#[derive(Debug)]
 pub struct Group {
     pub group_type: CourseType,
     pub participants: RefCell<LinkedList<Rc<Student>>>,
+    pub sessions: Cell<u64>,   // how often the group got a seat
+    pub rotating_roles: bool,  // the participants take turns as operator
+    pub lost: Cell<u64>,       // sessions cancelled by holidays
+    pub made_up: Cell<u64>,    // sessions that compensated a cancelled one
+    pub conflicts: Cell<u64>,  // turns skipped because the students were in another course
+    pub required: Option<u64>, // the sessions the group needs until all its students reach their quotas
+}
+
+/// A part of the students that shares the Zahnersatz courses with the other cohorts.
+/// Each cohort has its own rotation and may be restricted to some of the weekly slots.
+#[derive(Debug)]
+pub struct Cohort {
+    pub groups: LinkedList<Group>,
+    pub slots: Vec<JsonSlot>, // empty if all slots are allowed
+}
+
+/// a regular course that was dropped or moved to another day because of a holiday
+#[derive(Debug)]
+pub struct HolidayChange {
+    pub week: u64,
+    pub day: usize, // 0 is monday
+    pub course_type: CourseType,
+    pub beginning: u8,
+    pub moved_to: Option<usize>,
+}
+
+/// the sessions of a student compared to its quotas, in the order of COURSE_TYPES
+#[derive(Debug)]
+pub struct StudentStatistics {
+    pub student: Rc<Student>,
+    pub sessions: [u64; 4],
+    pub quotas: [Option<u64>; 4],
+    pub credited: [u64; 4], // sessions of earlier semesters
+    pub exempt: [bool; 4],
+    pub lost: u64,    // sessions cancelled by holidays
+    pub made_up: u64, // and compensated
+}
+
+/// settings of the distribution that are the same for all rotations
+#[derive(Debug, Default)]
+pub struct Context {
+    pub week_number: u64,
+    pub makeup_priority: bool, // groups that lost sessions get the next free seats
+    pub quota_priority: bool,  // groups that reached their quotas leave the seats to the others
+    pub course_order: Vec<CourseType>, // the order the course types of a day are distributed in
+    pub explain: bool,                 // every seat decision is recorded
+    pub decisions: RefCell<Vec<JsonDecision>>,
+}
+
+/// all rotations of the distribution, every course type has its own
+#[derive(Debug)]
+pub struct Rotations {
+    pub curriculum: LinkedList<Group>,
+    pub exkurs: LinkedList<Group>,
+    pub zahnerhalt: LinkedList<Group>,
+    pub zahnersatz: Vec<Cohort>,
 }
 
 pub trait Occupation<'a> {
-    fn is_occupied(&self, course: &Course, day: &Day) -> bool;
+    /// the type of the course at the same time as the given one that keeps this busy
+    fn get_occupation(&self, course: &Course, day: &Day) -> Option<CourseType>;
 }
 
 ///////////////////////////////////////////////////
 //Types for Json (de)serialization
+//The keys can also be written in English, see schema.rs. Unknown keys are rejected.
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
 #[allow(non_snake_case)]
 pub struct JsonData {
+    #[serde(default, rename = "$schema", skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>, // lets editors find the schema of the input
+    #[serde(default)]
+    pub version: Option<u64>,
+    #[serde(alias = "studentCount")]
     pub studentenAnzahl: u64,
+    #[serde(alias = "weeks")]
     pub wochen: JsonWeeks,
+    #[serde(alias = "holidays")]
     pub feiertage: Vec<JsonHoliday>,
-    pub exkursGruppen: Vec<Vec<u64>>,
+    #[serde(default, alias = "exkursGroups")]
+    pub exkursGruppen: Vec<Vec<u64>>, // may be omitted if gruppenImport is given
+    #[serde(default, alias = "curriculumGroups")]
     pub curriculumGruppen: Vec<Vec<u64>>,
+    #[serde(default, alias = "rosterImport")]
+    pub gruppenImport: Option<JsonRoster>,
+    #[serde(default, alias = "groupFormation")]
+    pub gruppenBildung: Option<JsonGrouping>, // only used by alban groups
+    #[serde(default, alias = "zahnersatzSplit")]
+    pub zahnersatzAufteilung: JsonCohortSplit,
+    #[serde(default, alias = "zahnersatzPairs")]
+    pub zahnersatzPaare: JsonPairs,
+    #[serde(default, alias = "pins")]
+    pub fixierungen: Vec<JsonPin>,
+    #[serde(default, alias = "students")]
+    pub studenten: Vec<JsonStudent>,
+    #[serde(default, alias = "quotas")]
+    pub kontingente: JsonQuotas,
+    #[serde(default, alias = "holidayRules")]
+    pub feiertagsRegeln: JsonHolidayRules,
+    #[serde(default, alias = "makeUp")]
+    pub nachholen: Option<JsonMakeUp>,
+    #[serde(default, alias = "rotationSeed")]
+    pub rotationsStartwert: Option<u64>,
+    #[serde(default, alias = "order")]
+    pub reihenfolge: Option<JsonOrder>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
 #[allow(non_snake_case)]
 pub struct JsonWeeks {
+    #[serde(alias = "firstWeek")]
     pub kwAnfang: u64,
+    #[serde(alias = "lastWeek")]
     pub kwEnde: u64,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
 #[allow(non_snake_case)]
 pub struct JsonHoliday {
+    #[serde(alias = "week")]
     pub woche: u64,
+    #[serde(alias = "days")]
     pub tage: Vec<u64>,
+    #[serde(default, alias = "courses")]
+    pub kurse: Vec<CourseType>, // all courses if empty
+    #[serde(default, alias = "from")]
+    pub ab: Option<u8>,         // covers the courses beginning at or after this hour
+    #[serde(default, alias = "until")]
+    pub bis: Option<u8>,        // and before this hour
+}
+
+/// A roster of the campus management system the groups are read from. The columns are
+/// found by their header, the defaults are given in roster.rs.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonRoster {
+    #[serde(alias = "file")]
+    pub datei: String, // relative to the input file
+    #[serde(default, alias = "delimiter")]
+    pub trennzeichen: Option<char>, // detected from the header if omitted
+    #[serde(default, alias = "idColumn")]
+    pub kennungSpalte: Option<String>,
+    #[serde(default, alias = "nameColumn")]
+    pub nameSpalte: Option<String>,
+    #[serde(default, alias = "numberColumn")]
+    pub nummerSpalte: Option<String>, // the students are numbered in the order of the rows if omitted
+    #[serde(default, alias = "curriculumColumn")]
+    pub curriculumSpalte: Option<String>,
+    #[serde(default, alias = "exkursColumn")]
+    pub exkursSpalte: Option<String>,
+}
+
+/// How alban groups forms the Curriculum and Exkurs groups. The Exkurs groups are formed
+/// inside of the Curriculum groups unless verschachtelt is false.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonGrouping {
+    #[serde(default)]
+    pub curriculum: JsonGroupRules,
+    #[serde(default)]
+    pub exkurs: JsonGroupRules,
+    #[serde(default, alias = "nested")]
+    pub verschachtelt: Option<bool>,
+    #[serde(default, alias = "seed")]
+    pub startwert: Option<u64>, // shuffles the students before they are grouped
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonGroupRules {
+    #[serde(default, alias = "size")]
+    pub groesse: Option<u64>, // the target size, the groups differ by at most one student
+    #[serde(default, alias = "together")]
+    pub zusammen: Vec<Vec<u64>>, // students that have to be in the same group
+    #[serde(default, alias = "apart")]
+    pub getrennt: Vec<Vec<u64>>, // students that have to be in different groups
+}
+
+/// how the students are split into the Zahnersatz cohorts
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum JsonSplitMethod {
+    #[default]
+    Position,           // consecutive parts of the student list
+    #[serde(alias = "oddEven")]
+    GeradeUngerade,     // odd and even student numbers
+    #[serde(alias = "list")]
+    Liste,              // students listed in the cohort
+    #[serde(alias = "curriculumGroups")]
+    CurriculumGruppen,  // members of the curriculum groups listed in the cohort
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonCohortSplit {
+    #[serde(default, alias = "method")]
+    pub art: JsonSplitMethod,
+    #[serde(default, alias = "cohorts")]
+    pub haelften: Vec<JsonCohort>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonCohort {
+    #[serde(default, alias = "students")]
+    pub studenten: Vec<u64>,
+    #[serde(default, alias = "groups")]
+    pub gruppen: Vec<u64>,
+    #[serde(default, alias = "slots")]
+    pub termine: Vec<JsonSlot>,
+}
+
+/// Zahnersatz treatment pairs, students without a listed partner are paired automatically
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonPairs {
+    #[serde(default, alias = "active")]
+    pub aktiv: bool,
+    #[serde(default, alias = "pairs")]
+    pub paare: Vec<Vec<u64>>,
+}
+
+/// Pins a group to a course or forbids it. The group is either given by its number
+/// in the group list of the course type (Curriculum and Exkurs) or by its students.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonPin {
+    #[serde(alias = "week")]
+    pub woche: u64,
+    #[serde(alias = "day")]
+    pub tag: u64,
+    #[serde(alias = "course")]
+    pub kurs: CourseType,
+    #[serde(alias = "beginning")]
+    pub beginn: u8,
+    #[serde(default, alias = "group")]
+    pub gruppe: Option<u64>,
+    #[serde(default, alias = "students")]
+    pub studenten: Vec<u64>,
+    #[serde(default, alias = "forbidden")]
+    pub verboten: bool,
+}
+
+/// the enrollment of a student that does not visit the whole semester
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonStudent {
+    #[serde(alias = "number")]
+    pub nummer: u64,
+    #[serde(default, alias = "from")]
+    pub von: Option<u64>,
+    #[serde(default, alias = "until")]
+    pub bis: Option<u64>,
+    #[serde(default, alias = "extra", skip_serializing_if = "JsonQuotas::is_empty")]
+    pub zusaetzlich: JsonQuotas, // sessions the student has to visit on top of the quotas
+    #[serde(default, alias = "completed", skip_serializing_if = "JsonQuotas::is_empty")]
+    pub absolviert: JsonQuotas, // sessions of earlier semesters that count towards the quotas
+    #[serde(default, alias = "exempt", skip_serializing_if = "Vec::is_empty")]
+    pub befreit: Vec<CourseType>, // course types the student does not visit
+}
+
+/// the sessions every student should visit during the whole semester
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonQuotas {
+    #[serde(default)]
+    pub Curriculum: Option<u64>,
+    #[serde(default)]
+    pub Exkurs: Option<u64>,
+    #[serde(default)]
+    pub Zahnerhalt: Option<u64>,
+    #[serde(default)]
+    pub Zahnersatz: Option<u64>,
+}
+
+/// what happens to a course in a week with a holiday
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum JsonHolidayRule {
+    #[serde(alias = "wholeWeek")]
+    GanzeWoche,  // drop the course for the whole week
+    #[serde(alias = "holidayOnly")]
+    NurFeiertag, // drop it only on the holiday
+    #[serde(alias = "move")]
+    Verschieben, // move it to another day of the week
+}
+
+/// the holiday rules per course type, see get_holiday_changes for the defaults
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonHolidayRules {
+    #[serde(default)]
+    pub Curriculum: Option<JsonHolidayRule>,
+    #[serde(default)]
+    pub Exkurs: Option<JsonHolidayRule>,
+    #[serde(default)]
+    pub Zahnerhalt: Option<JsonHolidayRule>,
+    #[serde(default)]
+    pub Zahnersatz: Option<JsonHolidayRule>,
+}
+
+/// how sessions that were cancelled by holidays are compensated
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum JsonMakeUpMethod {
+    #[serde(alias = "priority")]
+    Vorrang, // the groups get the next free seats of their course type
+    #[serde(alias = "slots")]
+    Termine, // the groups visit additional make-up courses
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonMakeUp {
+    #[serde(alias = "method")]
+    pub art: JsonMakeUpMethod,
+    #[serde(default, alias = "slots")]
+    pub termine: Vec<JsonCourseSlot>,
+}
+
+/// the order the course types are distributed in every day
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonOrder {
+    #[serde(alias = "courses")]
+    pub kurse: Vec<CourseType>,
+    #[serde(default, alias = "rotate")]
+    pub rotieren: bool, // every week the next course type goes first
+}
+
+/// a single course of a week
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonCourseSlot {
+    #[serde(alias = "week")]
+    pub woche: u64,
+    #[serde(alias = "day")]
+    pub tag: u64,
+    #[serde(alias = "course")]
+    pub kurs: CourseType,
+    #[serde(alias = "beginning")]
+    pub beginn: u8,
+}
+
+/// a weekly recurring course slot
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+#[allow(non_snake_case)]
+pub struct JsonSlot {
+    #[serde(alias = "day")]
+    pub tag: u64,
+    #[serde(alias = "beginning")]
+    pub beginn: u8,
+}
+
+///////////////////////////////////////////////////
+//Types for the Json export of a distribution
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(non_snake_case)]
+pub struct JsonSchedule {
+    pub wochen: Vec<JsonScheduleWeek>,
+    pub rotationen: Vec<JsonRotation>, // the state after the last week
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(non_snake_case)]
+pub struct JsonScheduleWeek {
+    pub nummer: u64,
+    pub rotationen: Vec<JsonRotation>, // the state before the week was distributed
+    pub kurse: Vec<JsonScheduleCourse>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(non_snake_case)]
+pub struct JsonScheduleCourse {
+    pub tag: u64,
+    pub kurs: CourseType,
+    pub beginn: u8,
+    pub teilnehmer: Vec<u64>,
+    #[serde(default)]
+    pub behandler: Vec<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(non_snake_case)]
+pub struct JsonRotation {
+    pub name: String,
+    pub gruppen: Vec<JsonRotationGroup>, // in the order of the rotation
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[allow(non_snake_case)]
+pub struct JsonRotationGroup {
+    pub studenten: Vec<u64>,
+    pub sitzungen: u64,
+    #[serde(default)]
+    pub ausgefallen: u64,
+    #[serde(default)]
+    pub nachgeholt: u64,
+    #[serde(default)]
+    pub konflikte: u64,
+}
+
+/// the record of how a single seat of a course was given away
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(non_snake_case)]
+pub struct JsonDecision {
+    pub woche: u64,
+    pub tag: u64,
+    pub kurs: CourseType,
+    pub beginn: u8,
+    pub art: CourseKind,
+    pub platz: u64,
+    pub warteschlange: Vec<Vec<u64>>, // the rotation before the seat was given away
+    pub uebersprungen: Vec<JsonSkip>,
+    pub vergeben: Option<Vec<u64>>, // none if no group could take the seat
+    pub fixiert: bool,
+}
+
+/// a group that was passed over for a seat although it was in front of the rotation
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(non_snake_case)]
+pub struct JsonSkip {
+    pub studenten: Vec<u64>,
+    pub grund: JsonSkipReason,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum JsonSkipReason {
+    Belegt(CourseType),  // the students are in another course at the same time
+    Verboten,            // a fixierung keeps the group away
+    NichtEingeschrieben, // none of the students is enrolled this week
+    KeinAusfall,         // only groups that lost sessions may visit make-up courses
+    Vorrang,             // groups that lost sessions were prioritized
+    KontingentErreicht,  // the group reached its quotas and others still need sessions
+}
+
+/// the changes between two distributions
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(non_snake_case)]
+pub struct JsonDiff {
+    pub zusammenfassung: JsonDiffSummary,
+    pub studenten: Vec<JsonStudentDiff>,
+    pub termine: Vec<JsonSlotDiff>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(non_snake_case)]
+pub struct JsonDiffSummary {
+    pub hinzugefuegt: u64, // assignments of a student to a course
+    pub entfernt: u64,
+    pub studenten: u64, // students with at least one change
+    pub termine: u64,   // courses with at least one change
+}
+
+/// the courses a student was added to or removed from
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(non_snake_case)]
+pub struct JsonStudentDiff {
+    pub nummer: u64,
+    pub hinzugefuegt: Vec<JsonCourseSlot>,
+    pub entfernt: Vec<JsonCourseSlot>,
+}
+
+/// the students that were added to or removed from a course
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(non_snake_case)]
+pub struct JsonSlotDiff {
+    pub termin: JsonCourseSlot,
+    pub hinzugefuegt: Vec<u64>,
+    pub entfernt: Vec<u64>,
+}
+
+///////////////////////////////////////////////////
+//Types for the web interface
+
+/// the answer to an input or a request that failed
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[allow(non_snake_case)]
+pub struct JsonReport {
+    pub fehler: Vec<String>,   // the request was rejected because of these
+    pub probleme: Vec<String>, // the input was accepted, but the distribution may fail or be unusable
+}
+
+/// a number for every course type
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[allow(non_snake_case)]
+pub struct JsonCourseCounts {
+    pub Curriculum: u64,
+    pub Exkurs: u64,
+    pub Zahnerhalt: u64,
+    pub Zahnersatz: u64,
+}
+
+/// the sessions of a student compared with the quotas
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(non_snake_case)]
+pub struct JsonStudentStatistics {
+    pub nummer: u64,
+    pub besuche: JsonCourseCounts,
+    pub soll: JsonQuotas,
+    pub angerechnet: JsonCourseCounts, // sessions of earlier semesters
+    pub befreit: Vec<CourseType>,
+    pub ausgefallen: u64, // sessions cancelled by holidays
+    pub nachgeholt: u64,  // and compensated
+}
+
+/// the statistics of a distribution
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(non_snake_case)]
+pub struct JsonStatistics {
+    pub studenten: Vec<JsonStudentStatistics>,
+    pub konflikte: JsonCourseCounts, // groups that were passed over because of another course
+}
+
+/// the courses of a single student
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(non_snake_case)]
+pub struct JsonTimetable {
+    pub nummer: u64,
+    pub termine: Vec<JsonCourseSlot>,
+    pub behandler: Vec<JsonCourseSlot>, // the Zahnersatz courses the student operates in
+}
+
+///////////////////////////////////////////////////
+//Types for the attendance
+
+/// who attended the courses of a distribution
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[allow(non_snake_case)]
+pub struct JsonAttendance {
+    #[serde(default, alias = "untilWeek")]
+    pub bisWoche: Option<u64>, // the last week that took place, the last recorded one if omitted
+    #[serde(alias = "slots")]
+    pub termine: Vec<JsonSlotAttendance>,
+}
+
+/// The attendance of a single course. Participants that are not listed were present.
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(non_snake_case)]
+pub struct JsonSlotAttendance {
+    #[serde(flatten)]
+    pub termin: JsonCourseSlot,
+    #[serde(default, alias = "present")]
+    pub anwesend: Vec<u64>, // students that visited the course without being planned for it
+    #[serde(default, alias = "absent")]
+    pub abwesend: Vec<u64>,
+    #[serde(default, alias = "excused")]
+    pub entschuldigt: Vec<u64>,
+}
+
+/// the attendance of a student compared with the distribution and the quotas
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(non_snake_case)]
+pub struct JsonStudentAttendance {
+    pub nummer: u64,
+    pub geplant: JsonCourseCounts, // the planned sessions until bisWoche
+    pub besucht: JsonCourseCounts,
+    pub abwesend: JsonCourseCounts,
+    pub entschuldigt: JsonCourseCounts,
+    pub ausstehend: JsonCourseCounts, // the planned sessions after bisWoche
+    pub soll: JsonQuotas,
+    pub defizit: JsonCourseCounts, // missing to the quotas even if all remaining sessions are visited
+}
+
+/// the attendance of all students until a week
+#[derive(Serialize, Deserialize, Debug)]
+#[allow(non_snake_case)]
+pub struct JsonAttendanceReport {
+    pub bisWoche: u64,
+    pub studenten: Vec<JsonStudentAttendance>,
+    pub probleme: Vec<String>, // entries that do not match the distribution
 }
@@ -0,0 +1,345 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::LinkedList;
+use std::rc::Rc;
+
+use types::*;
+use parser::*;
+use pins::*;
+use statistics::{get_required, is_exempt};
+
+/// checks a single course of the schedule against the generated weeks and the groups
+fn verify_course(
+    weeks: &[Week],
+    changes: &[HolidayChange],
+    students: &LinkedList<Rc<Student>>,
+    rotations: &Rotations,
+    slot: &JsonCourseSlot,
+    scheduled: &JsonScheduleCourse,
+    violations: &mut Vec<String>,
+) {
+    let course = match find_course(weeks, slot.woche, slot.tag, slot.kurs, slot.beginn) {
+        Some(course) => course,
+        None => {
+            let on_holiday = changes.iter().any(|change| {
+                change.week == slot.woche
+                    && change.day as u64 + 1 == slot.tag
+                    && change.course_type == slot.kurs
+                    && change.beginning == slot.beginn
+            });
+            if on_holiday {
+                violations.push(format!("{}: the course is cancelled by a holiday", slot));
+            } else {
+                violations.push(format!("{}: there is no such course", slot));
+            }
+            return;
+        }
+    };
+    let mut participants = Vec::new();
+    for number in scheduled.teilnehmer.iter() {
+        if participants.iter().any(|student: &Rc<Student>| student.number == *number) {
+            violations.push(format!("{}: student {} is listed twice", slot, number));
+            continue;
+        }
+        match students.iter().find(|student| student.number == *number) {
+            Some(student) => {
+                if !student.is_enrolled(slot.woche) {
+                    violations.push(format!("{}: student {} is not enrolled", slot, number));
+                }
+                participants.push(student.clone());
+            }
+            None => violations.push(format!("{}: there is no student {}", slot, number)),
+        }
+    }
+    for number in scheduled.behandler.iter() {
+        if !scheduled.teilnehmer.contains(number) {
+            violations.push(format!("{}: operator {} is no participant", slot, number));
+        }
+    }
+    let (groups, cohort_slots): (Vec<&Group>, Vec<&Vec<JsonSlot>>) = match slot.kurs {
+        CourseType::Curriculum => (rotations.curriculum.iter().collect(), Vec::new()),
+        CourseType::Exkurs => (rotations.exkurs.iter().collect(), Vec::new()),
+        CourseType::Zahnerhalt => (rotations.zahnerhalt.iter().collect(), Vec::new()),
+        CourseType::Zahnersatz => rotations
+            .zahnersatz
+            .iter()
+            .flat_map(|cohort| cohort.groups.iter().map(move |group| (group, &cohort.slots)))
+            .unzip(),
+    };
+    let weekly_slot = JsonSlot {
+        tag: slot.tag,
+        beginn: slot.beginn,
+    };
+    let mut seated_groups = 0;
+    for (index, group) in groups.iter().enumerate() {
+        let members = group.participants.borrow();
+        if !members.iter().any(|member| participants.contains(member)) {
+            continue;
+        }
+        seated_groups += 1;
+        for member in members.iter() {
+            if member.is_enrolled(slot.woche) && !participants.contains(member) {
+                violations.push(format!(
+                    "{}: student {} is missing, the rest of the group takes part",
+                    slot, member.number
+                ));
+            }
+        }
+        if let Some(allowed) = cohort_slots.get(index) {
+            if !allowed.is_empty() && !allowed.contains(&weekly_slot) && !is_pinned(group, &course) {
+                violations.push(format!(
+                    "{}: the cohort of student {} may not use this slot",
+                    slot,
+                    members.front().map_or(0, |member| member.number)
+                ));
+            }
+        }
+    }
+    for student in participants.iter() {
+        if !groups
+            .iter()
+            .any(|group| group.participants.borrow().contains(student))
+        {
+            violations.push(format!(
+                "{}: student {} is in no {:?} group",
+                slot, student.number, slot.kurs
+            ));
+        }
+    }
+    if seated_groups > get_seat_count(slot.kurs) as usize {
+        violations.push(format!(
+            "{}: {} groups exceed the {} seats",
+            slot,
+            seated_groups,
+            get_seat_count(slot.kurs)
+        ));
+    }
+    for student in course.pinned.borrow().iter() {
+        if !participants.contains(student) {
+            violations.push(format!("{}: pinned student {} is missing", slot, student.number));
+        }
+    }
+    for student in course.forbidden.borrow().iter() {
+        if participants.contains(student) {
+            violations.push(format!(
+                "{}: student {} takes part although it is forbidden",
+                slot, student.number
+            ));
+        }
+    }
+}
+
+/// Checks a distribution against the input: every course has to exist (and may not be on
+/// a holiday), the seats and groups have to be respected, no student may visit two
+/// courses at the same time, the fixierungen have to be fulfilled and every student
+/// should reach its quotas.
+/// Returns a description of every violation with its location.
+pub fn verify(parsed_data: &JsonData, schedule: &JsonSchedule) -> Vec<String> {
+    let mut violations = Vec::new();
+    let changes = get_holiday_changes(parsed_data);
+    let weeks = get_weeks(parsed_data, &changes);
+    let students = get_students(parsed_data);
+    let rotations = get_rotations(parsed_data, &students);
+    if let Err(errors) = apply_pins(parsed_data, &weeks, &students, &rotations) {
+        violations.extend(errors);
+    }
+    // the courses every student visits, to find double bookings and count the sessions
+    let mut visits: Vec<(u64, JsonCourseSlot)> = Vec::new();
+    for week in schedule.wochen.iter() {
+        if !weeks.iter().any(|generated| generated.number == week.nummer) {
+            violations.push(format!("KW {}: the week is not part of the semester", week.nummer));
+            continue;
+        }
+        for scheduled in week.kurse.iter() {
+            let slot = JsonCourseSlot {
+                woche: week.nummer,
+                tag: scheduled.tag,
+                kurs: scheduled.kurs,
+                beginn: scheduled.beginn,
+            };
+            verify_course(
+                &weeks,
+                &changes,
+                &students,
+                &rotations,
+                &slot,
+                scheduled,
+                &mut violations,
+            );
+            for number in scheduled.teilnehmer.iter() {
+                if let Some(&(_, other)) = visits.iter().find(|&&(student, other)| {
+                    student == *number
+                        && other.woche == slot.woche
+                        && other.tag == slot.tag
+                        && other.beginn == slot.beginn
+                        && other.kurs != slot.kurs
+                }) {
+                    violations.push(format!(
+                        "{}: student {} is in {:?} at the same time",
+                        slot, number, other.kurs
+                    ));
+                }
+                visits.push((*number, slot));
+            }
+        }
+    }
+    for student in students.iter() {
+        for course_type in COURSE_TYPES.iter() {
+            let sessions = visits
+                .iter()
+                .filter(|&&(number, slot)| number == student.number && slot.kurs == *course_type)
+                .count() as u64;
+            if is_exempt(parsed_data, student.number, *course_type) {
+                if sessions > 0 {
+                    violations.push(format!(
+                        "student {}: exempt from {:?} but in {} sessions",
+                        student.number, course_type, sessions
+                    ));
+                }
+                continue;
+            }
+            // the sessions of earlier semesters are not in the distribution
+            let quota = match get_required(parsed_data, student, *course_type) {
+                Some(quota) => quota,
+                None => continue,
+            };
+            if sessions < quota {
+                violations.push(format!(
+                    "student {}: only {} of {} {:?} sessions",
+                    student.number, sessions, quota, course_type
+                ));
+            }
+        }
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate serde_json;
+
+    use self::serde_json::Value;
+
+    use super::*;
+    use distribution::Options;
+    use fixtures::{distribute_quietly, get_input};
+
+    fn get_schedule() -> JsonSchedule {
+        distribute_quietly(&get_input(serde_json::json!({})), Options::default()).schedule
+    }
+
+    fn get_course(schedule: &mut JsonSchedule, week: u64, day: u64, course_type: CourseType) -> &mut JsonScheduleCourse {
+        schedule
+            .wochen
+            .iter_mut()
+            .find(|scheduled| scheduled.nummer == week)
+            .unwrap()
+            .kurse
+            .iter_mut()
+            .find(|course| course.tag == day && course.kurs == course_type && course.beginn == 7)
+            .unwrap()
+    }
+
+    fn assert_reported(violations: &[String], expected: &str) {
+        assert!(
+            violations.iter().any(|violation| violation.contains(expected)),
+            "{} is missing in {:#?}",
+            expected,
+            violations
+        );
+    }
+
+    #[test]
+    fn accepts_the_distribution_of_the_input() {
+        assert_eq!(verify(&get_input(serde_json::json!({})), &get_schedule()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_courses_that_do_not_exist() {
+        let mut schedule = get_schedule();
+        let mut course = get_course(&mut schedule, 14, 1, CourseType::Zahnerhalt).clone();
+        course.beginn = 9;
+        schedule.wochen[2].kurse.push(course.clone());
+        // monday of KW 12 is a holiday
+        course.beginn = 7;
+        schedule.wochen[0].kurse.push(course);
+        let mut week = schedule.wochen[2].clone();
+        week.nummer = 30;
+        schedule.wochen.push(week);
+        let violations = verify(&get_input(serde_json::json!({})), &schedule);
+        assert_reported(&violations, "KW 14 Montag     Zahnerhalt  9: there is no such course");
+        assert_reported(&violations, "KW 12 Montag     Zahnerhalt  7: the course is cancelled by a holiday");
+        assert_reported(&violations, "KW 30: the week is not part of the semester");
+    }
+
+    #[test]
+    fn reports_wrong_participants() {
+        let mut schedule = get_schedule();
+        {
+            // the participants are 11, 22, 5, 8, 18, 20, 21, 23 and 10
+            let course = get_course(&mut schedule, 14, 1, CourseType::Zahnerhalt);
+            course.teilnehmer.extend([11, 99, 1].iter());
+            course.behandler.push(2);
+        }
+        // the exkurs group of 9 and 19 and the curriculum group of 1, 7, 24, 2 and 16
+        get_course(&mut schedule, 14, 1, CourseType::Exkurs).teilnehmer.retain(|student| *student != 19);
+        get_course(&mut schedule, 14, 1, CourseType::Curriculum).teilnehmer.push(3);
+        let violations = verify(&get_input(serde_json::json!({})), &schedule);
+        let course = "KW 14 Montag     Zahnerhalt  7:";
+        assert_reported(&violations, &format!("{} student 11 is listed twice", course));
+        assert_reported(&violations, &format!("{} there is no student 99", course));
+        assert_reported(&violations, &format!("{} operator 2 is no participant", course));
+        assert_reported(&violations, "student 1 is in Curriculum at the same time");
+        assert_reported(&violations, "student 19 is missing, the rest of the group takes part");
+        assert_reported(&violations, "KW 14 Montag     Curriculum  7: 2 groups exceed the 1 seats");
+    }
+
+    #[test]
+    fn reports_violations_of_the_input() {
+        let schedule = get_schedule();
+        let check = |changes: Value, expected: &str| {
+            assert_reported(&verify(&get_input(changes), &schedule), expected)
+        };
+        check(serde_json::json!({"studenten": [{"nummer": 1, "von": 20}]}), "student 1 is not enrolled");
+        check(
+            serde_json::json!({"studenten": [{"nummer": 7, "befreit": ["Exkurs"]}]}),
+            "student 7: exempt from Exkurs but in",
+        );
+        check(serde_json::json!({"kontingente": {"Zahnerhalt": 50}}), "of 50 Zahnerhalt sessions");
+        check(
+            serde_json::json!({"curriculumGruppen": [
+                [1, 7, 24, 2], [3, 26, 15, 23, 22, 5], [10, 17, 21, 12, 25], [14, 19, 9, 8, 20], [4, 6, 13, 18, 11]
+            ]}),
+            "student 16 is in no Curriculum group",
+        );
+        check(
+            serde_json::json!({"zahnersatzAufteilung": {"haelften": [{"termine": [{"tag": 1, "beginn": 7}]}, {}]}}),
+            "may not use this slot",
+        );
+        let pin = |students: Value, forbidden: bool| {
+            serde_json::json!({"fixierungen": [{
+                "woche": 14, "tag": 1, "kurs": "Zahnerhalt", "beginn": 7, "studenten": students, "verboten": forbidden
+            }]})
+        };
+        check(pin(serde_json::json!([3]), false), "KW 14 Montag     Zahnerhalt  7: pinned student 3 is missing");
+        check(pin(serde_json::json!([11]), true), "student 11 takes part although it is forbidden");
+        check(
+            serde_json::json!({"fixierungen": [{"woche": 14, "tag": 1, "kurs": "Zahnerhalt", "beginn": 9, "studenten": [3]}]}),
+            "there is no such course",
+        );
+    }
+}
@@ -0,0 +1,273 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Tests of the json interface of alban serve. Every test starts its own server in a
+//! directory of its own with a copy of the input of the repository.
+
+extern crate alban;
+extern crate serde;
+extern crate serde_json;
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use alban::types::*;
+
+struct TestServer {
+    child: Child,
+    port: u16,
+    directory: PathBuf,
+}
+
+impl TestServer {
+    fn start(name: &str) -> TestServer {
+        let directory = std::env::temp_dir().join(format!("alban-api-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        fs::copy(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("input.json"),
+            directory.join("input.json"),
+        )
+        .unwrap();
+        // a port the system considers free
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let child = Command::new(env!("CARGO_BIN_EXE_alban"))
+            .args(["serve", "--port", &port.to_string()])
+            .current_dir(&directory)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap();
+        let server = TestServer {
+            child,
+            port,
+            directory,
+        };
+        let start = Instant::now();
+        while TcpStream::connect(("127.0.0.1", port)).is_err() {
+            assert!(start.elapsed() < Duration::from_secs(10), "the server did not start");
+            thread::sleep(Duration::from_millis(50));
+        }
+        server
+    }
+
+    /// Sends a request and returns the status and the body of the answer. HTTP/1.0 keeps
+    /// the answer in one piece instead of chunks.
+    fn request(&self, method: &str, path: &str, body: &str) -> (u16, String) {
+        self.request_with_headers(method, path, "Host: 127.0.0.1\r\n", body)
+    }
+
+    /// a request with the given header lines instead of the host 127.0.0.1
+    fn request_with_headers(&self, method: &str, path: &str, headers: &str, body: &str) -> (u16, String) {
+        let mut stream = TcpStream::connect(("127.0.0.1", self.port)).unwrap();
+        write!(
+            stream,
+            "{} {} HTTP/1.0\r\n{}Content-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            headers,
+            body.len(),
+            body
+        )
+        .unwrap();
+        let mut answer = String::new();
+        stream.read_to_string(&mut answer).unwrap();
+        let status = answer
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .unwrap();
+        let body = match answer.find("\r\n\r\n") {
+            Some(position) => answer[position + 4..].to_string(),
+            None => String::new(),
+        };
+        (status, body)
+    }
+
+    fn request_json<T: serde::de::DeserializeOwned>(&self, method: &str, path: &str, body: &str) -> (u16, T) {
+        let (status, body) = self.request(method, path, body);
+        match serde_json::from_str(&body) {
+            Ok(value) => (status, value),
+            Err(error) => panic!("{} {} answered {} with {}: {}", method, path, status, body, error),
+        }
+    }
+
+    fn read_input(&self) -> String {
+        fs::read_to_string(self.directory.join("input.json")).unwrap()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
+
+#[test]
+fn serves_and_checks_the_input() {
+    let server = TestServer::start("input");
+    let original = server.read_input();
+
+    let (status, input): (u16, Value) = server.request_json("GET", "/api/input", "");
+    assert_eq!(status, 200);
+    assert_eq!(input, serde_json::from_str::<Value>(&original).unwrap());
+
+    let (status, report): (u16, JsonReport) = server.request_json("POST", "/api/validate", &original);
+    assert_eq!(status, 200);
+    assert!(report.fehler.is_empty() && report.probleme.is_empty(), "{:?}", report);
+
+    let mut larger = input.clone();
+    larger["studentenAnzahl"] = Value::from(27);
+    let (status, report): (u16, JsonReport) = server.request_json("POST", "/api/validate", &larger.to_string());
+    assert_eq!(status, 200);
+    assert!(report.probleme.contains(&"student 27 is in no Curriculum group".to_string()));
+
+    let (status, report): (u16, JsonReport) = server.request_json("PUT", "/api/input", r#"{"studentenZahl": 3}"#);
+    assert_eq!(status, 422);
+    assert_eq!(report.fehler, vec!["unknown key: studentenZahl".to_string()]);
+
+    let (status, _): (u16, JsonReport) = server.request_json("PUT", "/api/input", "{ not json");
+    assert_eq!(status, 400);
+    // neither validating nor rejected inputs change the file
+    assert_eq!(server.read_input(), original);
+
+    let (status, report): (u16, JsonReport) = server.request_json("PUT", "/api/input", &larger.to_string());
+    assert_eq!(status, 200);
+    assert!(!report.probleme.is_empty());
+    let (_, saved): (u16, Value) = server.request_json("GET", "/api/input", "");
+    assert_eq!(saved["studentenAnzahl"], Value::from(27));
+    assert!(server.directory.join("input.json.bak").exists());
+}
+
+#[test]
+fn distributes_the_input() {
+    let server = TestServer::start("distribute");
+
+    let (status, _): (u16, JsonReport) = server.request_json("GET", "/api/schedule", "");
+    assert_eq!(status, 404);
+
+    let (status, schedule): (u16, JsonSchedule) = server.request_json("POST", "/api/distribute", "");
+    assert_eq!(status, 200);
+    let weeks: Vec<u64> = schedule.wochen.iter().map(|week| week.nummer).collect();
+    assert_eq!(weeks, (12..26).collect::<Vec<u64>>());
+    assert!(server.directory.join("Alban says.txt").exists());
+    let written: JsonSchedule =
+        serde_json::from_str(&fs::read_to_string(server.directory.join("Alban says.json")).unwrap()).unwrap();
+    assert_eq!(
+        serde_json::to_value(&written).unwrap(),
+        serde_json::to_value(&schedule).unwrap()
+    );
+
+    let (status, fetched): (u16, JsonSchedule) = server.request_json("GET", "/api/schedule", "");
+    assert_eq!(status, 200);
+    assert_eq!(
+        serde_json::to_value(&fetched).unwrap(),
+        serde_json::to_value(&schedule).unwrap()
+    );
+
+    let (status, report): (u16, JsonReport) = server.request_json("POST", "/api/verify", "");
+    assert_eq!(status, 200);
+    assert!(report.fehler.is_empty(), "{:?}", report.fehler);
+
+    // a student in every course of the first week visits courses at the same time
+    let mut broken = schedule.clone();
+    for course in broken.wochen[0].kurse.iter_mut() {
+        if !course.teilnehmer.contains(&1) {
+            course.teilnehmer.push(1);
+        }
+    }
+    let body = serde_json::to_string(&broken).unwrap();
+    let (status, report): (u16, JsonReport) = server.request_json("POST", "/api/verify", &body);
+    assert_eq!(status, 200);
+    assert!(!report.fehler.is_empty());
+}
+
+#[test]
+fn reports_students_and_statistics() {
+    let server = TestServer::start("students");
+    let (_, schedule): (u16, JsonSchedule) = server.request_json("POST", "/api/distribute", "");
+
+    let (status, timetable): (u16, JsonTimetable) = server.request_json("GET", "/api/students/1", "");
+    assert_eq!(status, 200);
+    assert_eq!(timetable.nummer, 1);
+    let expected = schedule
+        .wochen
+        .iter()
+        .map(|week| week.kurse.iter().filter(|course| course.teilnehmer.contains(&1)).count())
+        .sum::<usize>();
+    assert_eq!(timetable.termine.len(), expected);
+    assert!(timetable
+        .termine
+        .windows(2)
+        .all(|slots| (slots[0].woche, slots[0].tag, slots[0].beginn) <= (slots[1].woche, slots[1].tag, slots[1].beginn)));
+
+    let (status, _): (u16, JsonReport) = server.request_json("GET", "/api/students/27", "");
+    assert_eq!(status, 404);
+    let (status, _): (u16, JsonReport) = server.request_json("GET", "/api/students/one", "");
+    assert_eq!(status, 404);
+
+    let (status, statistics): (u16, JsonStatistics) = server.request_json("GET", "/api/statistics", "");
+    assert_eq!(status, 200);
+    assert_eq!(statistics.studenten.len(), 26);
+    let first = &statistics.studenten[0];
+    assert_eq!(first.nummer, 1);
+    let sessions = first.besuche.Curriculum + first.besuche.Exkurs + first.besuche.Zahnerhalt + first.besuche.Zahnersatz;
+    assert_eq!(sessions as usize, timetable.termine.len());
+}
+
+#[test]
+fn rejects_unknown_requests() {
+    let server = TestServer::start("unknown");
+    let (status, report): (u16, JsonReport) = server.request_json("GET", "/api/nothing", "");
+    assert_eq!(status, 404);
+    assert_eq!(report.fehler, vec!["there is no GET /api/nothing".to_string()]);
+    let (status, _): (u16, JsonReport) = server.request_json("DELETE", "/api/input", "");
+    assert_eq!(status, 404);
+    // files out of the assets are not served
+    let (status, _) = server.request("GET", "/assets/../input.json", "");
+    assert_eq!(status, 404);
+    let (status, page) = server.request("GET", "/", "");
+    assert_eq!(status, 200);
+    assert!(page.contains("<title>Alban</title>"));
+}
+
+#[test]
+fn only_answers_pages_of_localhost() {
+    let server = TestServer::start("origin");
+    let local = format!("Host: localhost:{}\r\nOrigin: http://localhost:{}\r\n", server.port, server.port);
+    let (status, _) = server.request_with_headers("GET", "/api/input", &local, "");
+    assert_eq!(status, 200);
+    // a name of another site that was rebound to 127.0.0.1
+    let (status, body) = server.request_with_headers("POST", "/api/distribute", "Host: evil.example\r\n", "");
+    assert_eq!(status, 403);
+    assert!(body.contains("only answers requests to localhost, not evil.example"), "{}", body);
+    // a page of another site that sends its request to the interface
+    let foreign = "Host: 127.0.0.1\r\nOrigin: http://evil.example\r\n";
+    let (status, _) = server.request_with_headers("POST", "/api/distribute", foreign, "");
+    assert_eq!(status, 403);
+    let (status, _) = server.request_with_headers("GET", "/", "", "");
+    assert_eq!(status, 403);
+    assert!(!server.directory.join("Alban says.json").exists());
+}
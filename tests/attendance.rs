@@ -0,0 +1,186 @@
+// Alban is a program to distribute dentistry students to their courses
+// Copyright (C) 2017 Tom Meyer
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Tests of the comparison of the attendance with a distribution of the input of the
+//! repository.
+
+extern crate alban;
+extern crate serde_json;
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use alban::attendance::*;
+use alban::distribution::*;
+use alban::parser::*;
+use alban::types::*;
+
+fn get_input() -> (Value, JsonData) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("input.json");
+    let mut value: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    value["kontingente"] = serde_json::json!({"Zahnerhalt": 24});
+    let data = match parse_value(&value) {
+        Ok(data) => data,
+        Err(errors) => panic!("{:?}", errors),
+    };
+    (value, data)
+}
+
+fn distribute_quietly(data: &JsonData) -> JsonSchedule {
+    let options = Options {
+        quiet: true,
+        ..Options::default()
+    };
+    distribute(data, &options).schedule
+}
+
+#[test]
+fn compares_the_attendance_with_the_plan() {
+    let (_, data) = get_input();
+    let schedule = distribute_quietly(&data);
+    let course = schedule.wochen[2]
+        .kurse
+        .iter()
+        .find(|course| course.kurs == CourseType::Zahnerhalt)
+        .unwrap()
+        .clone();
+    let (absent, excused) = (course.teilnehmer[0], course.teilnehmer[1]);
+    let visitor = (1..27).find(|student| !course.teilnehmer.contains(student)).unwrap();
+    let slot = JsonCourseSlot {
+        woche: schedule.wochen[2].nummer,
+        tag: course.tag,
+        kurs: course.kurs,
+        beginn: course.beginn,
+    };
+    let attendance = JsonAttendance {
+        bisWoche: Some(slot.woche),
+        termine: vec![JsonSlotAttendance {
+            termin: slot,
+            anwesend: vec![visitor],
+            abwesend: vec![absent],
+            entschuldigt: vec![excused],
+        }],
+    };
+    let report = reconcile(&data, &schedule, &attendance);
+    assert!(report.probleme.is_empty(), "{:?}", report.probleme);
+    let get = |number: u64| report.studenten.iter().find(|student| student.nummer == number).unwrap();
+    assert_eq!(get(absent).abwesend.Zahnerhalt, 1);
+    assert_eq!(get(absent).besucht.Zahnerhalt + 1, get(absent).geplant.Zahnerhalt);
+    assert_eq!(get(excused).entschuldigt.Zahnerhalt, 1);
+    assert_eq!(get(visitor).besucht.Zahnerhalt, get(visitor).geplant.Zahnerhalt + 1);
+    // every planned session is either in the recorded weeks or still to come
+    for student in report.studenten.iter() {
+        let planned: u64 = schedule
+            .wochen
+            .iter()
+            .flat_map(|week| week.kurse.iter())
+            .filter(|course| course.kurs == CourseType::Zahnerhalt && course.teilnehmer.contains(&student.nummer))
+            .count() as u64;
+        assert_eq!(student.geplant.Zahnerhalt + student.ausstehend.Zahnerhalt, planned);
+    }
+}
+
+#[test]
+fn prioritizes_the_students_with_a_deficit() {
+    let (mut value, data) = get_input();
+    let schedule = distribute_quietly(&data);
+    let count = |student: u64| {
+        schedule
+            .wochen
+            .iter()
+            .flat_map(|week| week.kurse.iter())
+            .filter(|course| course.kurs == CourseType::Zahnerhalt && course.teilnehmer.contains(&student))
+            .count()
+    };
+    // the student with the most Zahnerhalt sessions can miss one without a deficit
+    let absent = (1..27).max_by_key(|student| count(*student)).unwrap();
+    let (week, course) = schedule
+        .wochen
+        .iter()
+        .flat_map(|week| week.kurse.iter().map(move |course| (week.nummer, course)))
+        .find(|&(_, course)| course.kurs == CourseType::Zahnerhalt && course.teilnehmer.contains(&absent))
+        .unwrap();
+    let attendance = JsonAttendance {
+        bisWoche: Some(week),
+        termine: vec![JsonSlotAttendance {
+            termin: JsonCourseSlot {
+                woche: week,
+                tag: course.tag,
+                kurs: course.kurs,
+                beginn: course.beginn,
+            },
+            anwesend: Vec::new(),
+            abwesend: vec![absent],
+            entschuldigt: Vec::new(),
+        }],
+    };
+    let report = reconcile(&data, &schedule, &attendance);
+    let get = |number: u64| report.studenten.iter().find(|student| student.nummer == number).unwrap();
+    assert_eq!(get(absent).abwesend.Zahnerhalt, 1);
+    assert_eq!(get(absent).defizit.total(), 0);
+    // the quota is more than the distribution gives some of the students
+    let short: Vec<u64> = report
+        .studenten
+        .iter()
+        .filter(|student| student.defizit.Zahnerhalt > 0)
+        .map(|student| student.nummer)
+        .collect();
+    assert!(!short.is_empty());
+
+    let prioritized = add_deficits(&mut value, &report);
+    assert_eq!(prioritized, short);
+    let replan = parse_value(&value).unwrap();
+    for student in report.studenten.iter() {
+        let extra = replan
+            .studenten
+            .iter()
+            .find(|entry| entry.nummer == student.nummer)
+            .map(|entry| entry.zusaetzlich);
+        if student.defizit.Zahnerhalt > 0 {
+            assert_eq!(extra.and_then(|extra| extra.Zahnerhalt), Some(student.defizit.Zahnerhalt));
+            // courses without a deficit are not made up
+            assert_eq!(extra.and_then(|extra| extra.Curriculum), None);
+        } else {
+            assert_eq!(extra, None, "student {} has no deficit", student.nummer);
+        }
+    }
+}
+
+#[test]
+fn reports_entries_that_do_not_match() {
+    let (_, data) = get_input();
+    let schedule = distribute_quietly(&data);
+    let slot = JsonCourseSlot {
+        woche: schedule.wochen[0].nummer,
+        tag: 6,
+        kurs: CourseType::Exkurs,
+        beginn: 7,
+    };
+    let attendance = JsonAttendance {
+        bisWoche: None,
+        termine: vec![JsonSlotAttendance {
+            termin: slot,
+            anwesend: Vec::new(),
+            abwesend: vec![1],
+            entschuldigt: Vec::new(),
+        }],
+    };
+    let report = reconcile(&data, &schedule, &attendance);
+    assert_eq!(report.bisWoche, slot.woche);
+    assert_eq!(report.probleme, vec![format!("{}: there is no such course in the distribution", slot)]);
+    assert!(report.studenten.iter().all(|student| student.abwesend.total() == 0));
+}